@@ -0,0 +1,326 @@
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Schema, Clone)]
+pub struct NumberValue {
+    pub value: f64,
+    pub unit: Option<String>,
+}
+
+#[derive(Schema, Clone)]
+pub enum AttributeValue {
+    Text(String),
+    Number(NumberValue),
+    Boolean(bool),
+    Enum(String),
+}
+
+impl AttributeValue {
+    pub fn kind(&self) -> AttributeKind {
+        match self {
+            AttributeValue::Text(_) => AttributeKind::Text,
+            AttributeValue::Number(_) => AttributeKind::Number,
+            AttributeValue::Boolean(_) => AttributeKind::Boolean,
+            AttributeValue::Enum(_) => AttributeKind::Enum,
+        }
+    }
+}
+
+// Typed product attribute, e.g. ram = 16 GB or touch-screen = true
+#[derive(Schema, Clone)]
+pub struct ProductAttribute {
+    pub name: String,
+    pub value: AttributeValue,
+}
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum AttributeKind {
+    Text,
+    Number,
+    Boolean,
+    Enum,
+}
+
+// Attribute definition of a schema, `unit` applies to numbers and `values` to enums
+#[derive(Schema, Clone)]
+pub struct AttributeDefinition {
+    pub name: String,
+    pub kind: AttributeKind,
+    pub unit: Option<String>,
+    pub values: Vec<String>,
+    pub required: bool,
+}
+
+#[derive(Schema, Clone)]
+pub struct AttributeSchemas {
+    pub category_schemas: HashMap<String, Vec<AttributeDefinition>>,
+    pub tag_schemas: HashMap<String, Vec<AttributeDefinition>>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AttributeSchemas {
+    fn new() -> Self {
+        Self {
+            category_schemas: HashMap::new(),
+            tag_schemas: HashMap::new(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    // Definitions applicable for the given categories and tags, first definition of a name wins
+    fn get_definitions(
+        &self,
+        category_ids: &[String],
+        tags: &[String],
+    ) -> Vec<AttributeDefinition> {
+        let mut names: HashSet<String> = HashSet::new();
+
+        category_ids
+            .iter()
+            .filter_map(|id| self.category_schemas.get(id))
+            .chain(tags.iter().filter_map(|tag| self.tag_schemas.get(tag)))
+            .flatten()
+            .filter(|definition| names.insert(definition.name.clone()))
+            .cloned()
+            .collect()
+    }
+}
+
+fn validate_definitions(definitions: &[AttributeDefinition]) -> Result<(), String> {
+    let mut names: HashSet<&str> = HashSet::new();
+
+    for definition in definitions {
+        if definition.name.trim().is_empty() {
+            return Err("Attribute name is empty".to_string());
+        }
+        if !names.insert(definition.name.as_str()) {
+            return Err(format!(
+                "Attribute {} defined more than once",
+                definition.name
+            ));
+        }
+        if definition.kind == AttributeKind::Enum && definition.values.is_empty() {
+            return Err(format!("Attribute {} has no enum values", definition.name));
+        }
+        if definition.kind != AttributeKind::Enum && !definition.values.is_empty() {
+            return Err(format!(
+                "Attribute {} has values, but is not an enum",
+                definition.name
+            ));
+        }
+        if definition.kind != AttributeKind::Number && definition.unit.is_some() {
+            return Err(format!(
+                "Attribute {} has unit, but is not a number",
+                definition.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Validate attributes against the definitions, number units are defaulted to the definition unit,
+// required attributes are not checked
+pub fn validate_attribute_values(
+    definitions: &[AttributeDefinition],
+    attributes: Vec<ProductAttribute>,
+) -> Result<Vec<ProductAttribute>, String> {
+    let mut names: HashSet<String> = HashSet::new();
+    let mut result = Vec::new();
+
+    for mut attribute in attributes {
+        if !names.insert(attribute.name.clone()) {
+            return Err(format!("Attribute {} set more than once", attribute.name));
+        }
+
+        let definition = definitions
+            .iter()
+            .find(|d| d.name == attribute.name)
+            .ok_or(format!(
+                "Attribute {} not defined for product categories or tags",
+                attribute.name
+            ))?;
+
+        if attribute.value.kind() != definition.kind {
+            return Err(format!(
+                "Attribute {} expected to be {:?}",
+                attribute.name, definition.kind
+            ));
+        }
+
+        match &mut attribute.value {
+            AttributeValue::Number(number) => {
+                if !number.value.is_finite() {
+                    return Err(format!(
+                        "Attribute {} is not a valid number",
+                        attribute.name
+                    ));
+                }
+                match (&number.unit, &definition.unit) {
+                    (None, unit) => number.unit = unit.clone(),
+                    (Some(unit), Some(expected)) if unit != expected => {
+                        return Err(format!(
+                            "Attribute {} expected in unit {}",
+                            attribute.name, expected
+                        ))
+                    }
+                    (Some(_), None) => {
+                        return Err(format!("Attribute {} has no unit", attribute.name))
+                    }
+                    _ => (),
+                }
+            }
+            AttributeValue::Enum(value) if !definition.values.contains(value) => {
+                return Err(format!(
+                    "Value {} not valid for attribute {}",
+                    value, attribute.name
+                ));
+            }
+            _ => (),
+        }
+
+        result.push(attribute);
+    }
+
+    Ok(result)
+}
+
+// Validate attributes against the definitions, all required attributes have to be set
+pub fn validate_attributes(
+    definitions: &[AttributeDefinition],
+    attributes: Vec<ProductAttribute>,
+) -> Result<Vec<ProductAttribute>, String> {
+    let attributes = validate_attribute_values(definitions, attributes)?;
+
+    if let Some(missing) = definitions
+        .iter()
+        .find(|d| d.required && !attributes.iter().any(|a| a.name == d.name))
+    {
+        return Err(format!("Attribute {} is required", missing.name));
+    }
+
+    Ok(attributes)
+}
+
+#[derive(Schema, Clone)]
+pub struct AttributeSchemaNotValidError {
+    pub message: String,
+}
+
+#[agent_definition]
+trait AttributeSchemaAgent {
+    fn new() -> Self;
+
+    fn get_category_schema(&self, category_id: String) -> Vec<AttributeDefinition>;
+
+    fn get_tag_schema(&self, tag: String) -> Vec<AttributeDefinition>;
+
+    fn get_definitions(
+        &self,
+        category_ids: Vec<String>,
+        tags: Vec<String>,
+    ) -> Vec<AttributeDefinition>;
+
+    fn get_attribute_names(&self) -> Vec<String>;
+
+    fn set_category_schema(
+        &mut self,
+        category_id: String,
+        definitions: Vec<AttributeDefinition>,
+    ) -> Result<(), AttributeSchemaNotValidError>;
+
+    fn set_tag_schema(
+        &mut self,
+        tag: String,
+        definitions: Vec<AttributeDefinition>,
+    ) -> Result<(), AttributeSchemaNotValidError>;
+}
+
+struct AttributeSchemaAgentImpl {
+    state: AttributeSchemas,
+}
+
+#[agent_implementation]
+impl AttributeSchemaAgent for AttributeSchemaAgentImpl {
+    fn new() -> Self {
+        AttributeSchemaAgentImpl {
+            state: AttributeSchemas::new(),
+        }
+    }
+
+    fn get_category_schema(&self, category_id: String) -> Vec<AttributeDefinition> {
+        self.state
+            .category_schemas
+            .get(&category_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn get_tag_schema(&self, tag: String) -> Vec<AttributeDefinition> {
+        self.state
+            .tag_schemas
+            .get(&tag)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn get_definitions(
+        &self,
+        category_ids: Vec<String>,
+        tags: Vec<String>,
+    ) -> Vec<AttributeDefinition> {
+        self.state.get_definitions(&category_ids, &tags)
+    }
+
+    fn get_attribute_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .state
+            .category_schemas
+            .values()
+            .chain(self.state.tag_schemas.values())
+            .flatten()
+            .map(|definition| definition.name.to_lowercase())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn set_category_schema(
+        &mut self,
+        category_id: String,
+        definitions: Vec<AttributeDefinition>,
+    ) -> Result<(), AttributeSchemaNotValidError> {
+        println!("Setting attribute schema for category {}", category_id);
+
+        validate_definitions(&definitions)
+            .map_err(|message| AttributeSchemaNotValidError { message })?;
+
+        if definitions.is_empty() {
+            self.state.category_schemas.remove(&category_id);
+        } else {
+            self.state.category_schemas.insert(category_id, definitions);
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn set_tag_schema(
+        &mut self,
+        tag: String,
+        definitions: Vec<AttributeDefinition>,
+    ) -> Result<(), AttributeSchemaNotValidError> {
+        println!("Setting attribute schema for tag {}", tag);
+
+        validate_definitions(&definitions)
+            .map_err(|message| AttributeSchemaNotValidError { message })?;
+
+        if definitions.is_empty() {
+            self.state.tag_schemas.remove(&tag);
+        } else {
+            self.state.tag_schemas.insert(tag, definitions);
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+}
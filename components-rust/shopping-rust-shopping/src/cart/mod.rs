@@ -1,11 +1,18 @@
 use crate::common::{Address, CURRENCY_DEFAULT, PRICING_ZONE_DEFAULT};
 use crate::order::{CreateOrder, OrderAgentClient, OrderItem};
 use crate::pricing::{PricingAgentClient, PricingItem};
-use crate::product::{Product, ProductAgentClient};
+use crate::pricing_query::PricingQueryAgentClient;
+use crate::product::{
+    get_bundle_component_items, BundleComponentError, BundleComponentItem, Product,
+    ProductAgentClient, ProductVariant, SelectedOption,
+};
+use crate::product_affinity::ProductAffinityAgentClient;
+use crate::product_query::get_products;
 use crate::shopping_assistant::ShoppingAssistantAgentClient;
 use email_address::EmailAddress;
-use futures::future::join;
+use futures::future::{join, join3, join_all};
 use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -71,6 +78,14 @@ impl Cart {
         self.updated_at = chrono::Utc::now();
     }
 
+    // Country the order is delivered to, the billing address is used if no shipping address is set
+    fn get_delivery_country(&self) -> Option<String> {
+        self.shipping_address
+            .as_ref()
+            .or(self.billing_address.as_ref())
+            .map(|address| address.country.clone())
+    }
+
     fn set_shipping_address(&mut self, address: Address) {
         self.shipping_address = Some(address);
         self.updated_at = chrono::Utc::now();
@@ -81,11 +96,17 @@ impl Cart {
         self.updated_at = chrono::Utc::now();
     }
 
-    fn update_item_quantity(&mut self, product_id: String, quantity: u32, add: bool) -> bool {
+    fn update_item_quantity(
+        &mut self,
+        product_id: String,
+        variant_id: Option<String>,
+        quantity: u32,
+        add: bool,
+    ) -> bool {
         let mut updated = false;
 
         for item in &mut self.items {
-            if item.product_id == product_id {
+            if item.is_item(&product_id, &variant_id) {
                 if add {
                     item.quantity += quantity;
                 } else {
@@ -102,11 +123,15 @@ impl Cart {
         updated
     }
 
-    fn remove_item(&mut self, product_id: String) -> bool {
-        let exist = self.items.iter().any(|item| item.product_id == product_id);
+    fn remove_item(&mut self, product_id: String, variant_id: Option<String>) -> bool {
+        let exist = self
+            .items
+            .iter()
+            .any(|item| item.is_item(&product_id, &variant_id));
 
         if exist {
-            self.items.retain(|item| item.product_id != product_id);
+            self.items
+                .retain(|item| !item.is_item(&product_id, &variant_id));
             self.recalculate_total();
         }
 
@@ -119,14 +144,26 @@ pub struct CartItem {
     pub product_id: String,
     pub product_name: String,
     pub product_brand: String,
+    pub variant_id: Option<String>,
+    pub options: Vec<SelectedOption>,
+    pub bundle_components: Vec<BundleComponentItem>,
     pub price: f32,
     pub quantity: u32,
 }
 
+impl CartItem {
+    fn is_item(&self, product_id: &str, variant_id: &Option<String>) -> bool {
+        self.product_id == product_id && self.variant_id == *variant_id
+    }
+}
+
 impl From<CartItem> for OrderItem {
     fn from(value: CartItem) -> Self {
         Self {
             product_id: value.product_id,
+            variant_id: value.variant_id,
+            options: value.options,
+            bundle_components: value.bundle_components,
             quantity: value.quantity,
             price: value.price,
             product_name: value.product_name,
@@ -190,6 +227,54 @@ impl ProductNotFoundError {
     }
 }
 #[derive(Schema, Clone)]
+pub struct ProductNotAvailableError {
+    pub message: String,
+    pub product_id: String,
+}
+
+impl ProductNotAvailableError {
+    fn new(product_id: String) -> ProductNotAvailableError {
+        ProductNotAvailableError {
+            message: "Product not available".to_string(),
+            product_id,
+        }
+    }
+}
+#[derive(Schema, Clone)]
+pub struct ProductNotSellableError {
+    pub message: String,
+    pub product_id: String,
+    pub zone: String,
+    pub country: Option<String>,
+}
+
+impl ProductNotSellableError {
+    fn new(product_id: String, zone: String, country: Option<String>) -> ProductNotSellableError {
+        ProductNotSellableError {
+            message: "Product not sellable in the zone or country".to_string(),
+            product_id,
+            zone,
+            country,
+        }
+    }
+}
+#[derive(Schema, Clone)]
+pub struct VariantNotFoundError {
+    pub message: String,
+    pub product_id: String,
+    pub variant_id: String,
+}
+
+impl VariantNotFoundError {
+    fn new(product_id: String, variant_id: String) -> VariantNotFoundError {
+        VariantNotFoundError {
+            message: "Variant not found".to_string(),
+            product_id,
+            variant_id,
+        }
+    }
+}
+#[derive(Schema, Clone)]
 pub struct EmailNotValidError {
     pub message: String,
 }
@@ -216,6 +301,9 @@ pub struct OrderCreateError {
 #[derive(Schema, Clone)]
 pub enum AddItemError {
     ProductNotFound(ProductNotFoundError),
+    ProductNotAvailable(ProductNotAvailableError),
+    ProductNotSellable(ProductNotSellableError),
+    VariantNotFound(VariantNotFoundError),
     PricingNotFound(PricingNotFoundError),
 }
 #[derive(Schema, Clone)]
@@ -235,10 +323,15 @@ pub enum UpdateEmailError {
 #[derive(Schema, Clone)]
 pub enum UpdateItemQuantityError {
     ItemNotFound(ItemNotFoundError),
+    ProductNotFound(ProductNotFoundError),
+    ProductNotAvailable(ProductNotAvailableError),
+    ProductNotSellable(ProductNotSellableError),
 }
 #[derive(Schema, Clone)]
 pub enum CheckoutError {
     ProductNotFound(ProductNotFoundError),
+    ProductNotAvailable(ProductNotAvailableError),
+    ProductNotSellable(ProductNotSellableError),
     PricingNotFound(PricingNotFoundError),
     EmptyItems(EmptyItemsError),
     EmptyEmail(EmptyEmailError),
@@ -269,11 +362,20 @@ fn generate_order_id() -> String {
     Uuid::new_v4().to_string()
 }
 
-fn get_cart_item(product: Product, pricing: PricingItem, quantity: u32) -> CartItem {
+fn get_cart_item(
+    product: Product,
+    variant: Option<ProductVariant>,
+    bundle_components: Vec<BundleComponentItem>,
+    pricing: PricingItem,
+    quantity: u32,
+) -> CartItem {
     CartItem {
         product_id: product.product_id,
         product_name: product.name,
         product_brand: product.brand,
+        variant_id: variant.as_ref().map(|v| v.variant_id.clone()),
+        options: variant.map(|v| v.options).unwrap_or_default(),
+        bundle_components,
         price: pricing.price,
         quantity,
     }
@@ -299,10 +401,67 @@ fn validate_cart(cart: Cart) -> Result<(), CheckoutError> {
     }
 }
 
+// Check that all products, including bundle components, are available and can be sold to the delivery country
+async fn validate_cart_availability(cart: &Cart) -> Result<(), CheckoutError> {
+    let zone = PRICING_ZONE_DEFAULT.to_string();
+    let country = cart.get_delivery_country();
+
+    let product_ids: Vec<String> = cart
+        .items
+        .iter()
+        .map(|item| item.product_id.clone())
+        .collect();
+
+    let products = get_products(&product_ids).await;
+
+    for (item, product) in cart.items.iter().zip(products) {
+        match product {
+            Some(product) if !product.is_available() => {
+                return Err(CheckoutError::ProductNotAvailable(
+                    ProductNotAvailableError::new(item.product_id.clone()),
+                ));
+            }
+            Some(product) if !product.is_sellable_in(&zone, country.as_deref()) => {
+                return Err(CheckoutError::ProductNotSellable(
+                    ProductNotSellableError::new(item.product_id.clone(), zone, country),
+                ));
+            }
+            Some(product) if product.is_bundle() => {
+                get_bundle_component_items(&product, &zone, country.as_deref())
+                    .await
+                    .map_err(|e| match e {
+                        BundleComponentError::ProductNotFound(id) => {
+                            CheckoutError::ProductNotFound(ProductNotFoundError::new(id))
+                        }
+                        BundleComponentError::ProductNotAvailable(id) => {
+                            CheckoutError::ProductNotAvailable(ProductNotAvailableError::new(id))
+                        }
+                        BundleComponentError::ProductNotSellable(id) => {
+                            CheckoutError::ProductNotSellable(ProductNotSellableError::new(
+                                id,
+                                zone.clone(),
+                                country.clone(),
+                            ))
+                        }
+                    })?;
+            }
+            Some(_) => (),
+            None => {
+                return Err(CheckoutError::ProductNotFound(ProductNotFoundError::new(
+                    item.product_id.clone(),
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 async fn create_order(order_id: String, cart: Cart) -> Result<String, CheckoutError> {
     println!("Creating order: {}", order_id);
 
     validate_cart(cart.clone())?;
+    validate_cart_availability(&cart).await?;
 
     let order = cart.into();
 
@@ -318,21 +477,50 @@ async fn create_order(order_id: String, cart: Cart) -> Result<String, CheckoutEr
     Ok(order_id)
 }
 
+async fn get_variant_price(item: CartItem, currency: String) -> Option<PricingItem> {
+    match item.variant_id {
+        Some(variant_id) => {
+            PricingAgentClient::get(item.product_id)
+                .get_variant_price(variant_id, currency, PRICING_ZONE_DEFAULT.to_string())
+                .await
+        }
+        None => None,
+    }
+}
+
 #[agent_definition]
 trait CartAgent {
     fn new(id: String) -> Self;
     async fn get_cart(&mut self) -> Option<Cart>;
+    fn get_order_ids(&self) -> Vec<String>;
     async fn add_item(&mut self, product_id: String, quantity: u32) -> Result<(), AddItemError>;
+    async fn add_variant_item(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+        quantity: u32,
+    ) -> Result<(), AddItemError>;
     async fn checkout(&mut self) -> Result<OrderConfirmation, CheckoutError>;
     fn update_email(&mut self, email: String) -> Result<(), UpdateEmailError>;
     fn clear(&mut self);
     fn remove_item(&mut self, product_id: String) -> Result<(), RemoveItemError>;
+    fn remove_variant_item(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+    ) -> Result<(), RemoveItemError>;
     fn update_billing_address(&mut self, address: Address) -> Result<(), UpdateAddressError>;
-    fn update_item_quantity(
+    async fn update_item_quantity(
         &mut self,
         product_id: String,
         quantity: u32,
     ) -> Result<(), UpdateItemQuantityError>;
+    async fn update_variant_item_quantity(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+        quantity: u32,
+    ) -> Result<(), UpdateItemQuantityError>;
     fn update_shipping_address(&mut self, address: Address) -> Result<(), UpdateAddressError>;
 }
 
@@ -349,6 +537,187 @@ impl CartAgentImpl {
     fn with_state<T>(&mut self, f: impl FnOnce(&mut Cart) -> T) -> T {
         f(self.get_state())
     }
+
+    async fn add_cart_item(
+        &mut self,
+        product_id: String,
+        variant_id: Option<String>,
+        quantity: u32,
+    ) -> Result<(), AddItemError> {
+        let state = self.get_state();
+
+        println!(
+            "Adding item with product {} variant {:?} to the cart of user {}",
+            product_id, variant_id, state.user_id
+        );
+
+        // The product is checked before adding to the quantity of an item already in the cart
+        let product_client = ProductAgentClient::get(product_id.clone());
+        let pricing_client = PricingAgentClient::get(product_id.clone());
+        let currency = state.currency.clone();
+        let zone = PRICING_ZONE_DEFAULT.to_string();
+        let country = state.get_delivery_country();
+
+        let (product, pricing) = join(product_client.get_product(), async {
+            match variant_id.clone() {
+                Some(variant_id) => {
+                    pricing_client
+                        .get_variant_price(variant_id, currency, zone.clone())
+                        .await
+                }
+                None => pricing_client.get_price(currency, zone.clone()).await,
+            }
+        })
+        .await;
+
+        match (product, pricing) {
+            (Some(product), _) if !product.is_available() => {
+                return Err(AddItemError::ProductNotAvailable(
+                    ProductNotAvailableError::new(product_id),
+                ));
+            }
+            (Some(product), _) if !product.is_sellable_in(&zone, country.as_deref()) => {
+                return Err(AddItemError::ProductNotSellable(
+                    ProductNotSellableError::new(product_id, zone, country),
+                ));
+            }
+            (Some(product), Some(pricing)) => {
+                let variant = variant_id
+                    .clone()
+                    .map(|variant_id| {
+                        product
+                            .get_variant(&variant_id)
+                            .ok_or(AddItemError::VariantNotFound(VariantNotFoundError::new(
+                                product_id.clone(),
+                                variant_id,
+                            )))
+                    })
+                    .transpose()?;
+
+                let bundle_components = if product.is_bundle() {
+                    get_bundle_component_items(&product, &zone, country.as_deref())
+                        .await
+                        .map_err(|e| match e {
+                            BundleComponentError::ProductNotFound(id) => {
+                                AddItemError::ProductNotFound(ProductNotFoundError::new(id))
+                            }
+                            BundleComponentError::ProductNotAvailable(id) => {
+                                AddItemError::ProductNotAvailable(ProductNotAvailableError::new(id))
+                            }
+                            BundleComponentError::ProductNotSellable(id) => {
+                                AddItemError::ProductNotSellable(ProductNotSellableError::new(
+                                    id,
+                                    zone.clone(),
+                                    country.clone(),
+                                ))
+                            }
+                        })?
+                } else {
+                    vec![]
+                };
+
+                let updated = state.update_item_quantity(
+                    product_id.clone(),
+                    variant_id.clone(),
+                    quantity,
+                    true,
+                );
+
+                if !updated {
+                    state.add_item(get_cart_item(
+                        product,
+                        variant,
+                        bundle_components,
+                        pricing,
+                        quantity,
+                    ));
+                }
+            }
+            (None, _) => {
+                return Err(AddItemError::ProductNotFound(ProductNotFoundError::new(
+                    product_id,
+                )));
+            }
+            _ => {
+                return Err(AddItemError::PricingNotFound(PricingNotFoundError::new(
+                    product_id,
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_cart_item(
+        &mut self,
+        product_id: String,
+        variant_id: Option<String>,
+    ) -> Result<(), RemoveItemError> {
+        self.with_state(|state| {
+            println!(
+                "Removing item with product {} variant {:?} from the cart of user {}",
+                product_id, variant_id, state.user_id
+            );
+
+            if state.remove_item(product_id.clone(), variant_id) {
+                Ok(())
+            } else {
+                Err(RemoveItemError::ItemNotFound(ItemNotFoundError::new(
+                    product_id,
+                )))
+            }
+        })
+    }
+
+    // The product is checked before the quantity is changed
+    async fn update_cart_item_quantity(
+        &mut self,
+        product_id: String,
+        variant_id: Option<String>,
+        quantity: u32,
+    ) -> Result<(), UpdateItemQuantityError> {
+        let state = self.get_state();
+
+        println!(
+            "Updating quantity of item with product {} variant {:?} to {} in the cart of user {}",
+            product_id, variant_id, quantity, state.user_id
+        );
+
+        if !state
+            .items
+            .iter()
+            .any(|item| item.is_item(&product_id, &variant_id))
+        {
+            return Err(UpdateItemQuantityError::ItemNotFound(
+                ItemNotFoundError::new(product_id),
+            ));
+        }
+
+        let zone = PRICING_ZONE_DEFAULT.to_string();
+        let country = state.get_delivery_country();
+
+        match ProductAgentClient::get(product_id.clone())
+            .get_product()
+            .await
+        {
+            Some(product) if !product.is_available() => {
+                Err(UpdateItemQuantityError::ProductNotAvailable(
+                    ProductNotAvailableError::new(product_id),
+                ))
+            }
+            Some(product) if !product.is_sellable_in(&zone, country.as_deref()) => {
+                Err(UpdateItemQuantityError::ProductNotSellable(
+                    ProductNotSellableError::new(product_id, zone, country),
+                ))
+            }
+            Some(_) => {
+                state.update_item_quantity(product_id, variant_id, quantity, false);
+                Ok(())
+            }
+            None => Err(UpdateItemQuantityError::ProductNotFound(
+                ProductNotFoundError::new(product_id),
+            )),
+        }
+    }
 }
 
 #[agent_implementation]
@@ -363,23 +732,97 @@ impl CartAgent for CartAgentImpl {
     async fn get_cart(&mut self) -> Option<Cart> {
         println!("Getting cart");
         if let Some(cart) = self.state.as_mut() {
-            let mut items = Vec::new();
-            for item in cart.items.clone() {
-                let product_id = item.product_id;
-                let quantity = item.quantity;
-
-                let product_client = ProductAgentClient::get(product_id.clone());
-                let pricing_client = PricingAgentClient::get(product_id.clone());
+            let product_ids: Vec<String> = cart
+                .items
+                .iter()
+                .map(|item| item.product_id.clone())
+                .collect();
+
+            // Variant lines are priced by the variant price
+            let priced_product_ids: Vec<String> = cart
+                .items
+                .iter()
+                .filter(|item| item.variant_id.is_none())
+                .map(|item| item.product_id.clone())
+                .collect();
+
+            let pricing_client = PricingQueryAgentClient::get();
+
+            let (products, prices, variant_prices) = join3(
+                get_products(&product_ids),
+                pricing_client.get_prices(
+                    priced_product_ids,
+                    cart.currency.clone(),
+                    PRICING_ZONE_DEFAULT.to_string(),
+                ),
+                join_all(
+                    cart.items
+                        .iter()
+                        .map(|item| get_variant_price(item.clone(), cart.currency.clone())),
+                ),
+            )
+            .await;
 
-                let (product, pricing) = join(
-                    product_client.get_product(),
-                    pricing_client
-                        .get_price(cart.currency.clone(), PRICING_ZONE_DEFAULT.to_string()),
-                )
-                .await;
+            // Lines of products which are not available anymore are dropped,
+            // bundle components are refreshed from the bundle products,
+            // components not found are left out, checkout rejects such bundles
+            let component_ids: Vec<String> = products
+                .iter()
+                .flatten()
+                .flat_map(|product| product.bundle_components.iter())
+                .map(|component| component.product_id.clone())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            let component_products: HashMap<String, Product> = get_products(&component_ids)
+                .await
+                .into_iter()
+                .flatten()
+                .map(|product| (product.product_id.clone(), product))
+                .collect();
 
-                if let (Some(product), Some(pricing)) = (product, pricing) {
-                    items.push(get_cart_item(product, pricing, quantity));
+            let mut items = Vec::new();
+            for ((item, product), variant_price) in
+                cart.items.iter().zip(products).zip(variant_prices)
+            {
+                let pricing = if item.variant_id.is_some() {
+                    variant_price
+                } else {
+                    prices
+                        .get(&item.product_id)
+                        .and_then(|result| result.price())
+                };
+
+                if let (Some(product), Some(pricing)) =
+                    (product.filter(|p| p.is_available()), pricing)
+                {
+                    let bundle_components: Vec<BundleComponentItem> = product
+                        .bundle_components
+                        .iter()
+                        .filter_map(|component| {
+                            component_products
+                                .get(&component.product_id)
+                                .map(|p| BundleComponentItem::new(component, p.clone()))
+                        })
+                        .collect();
+
+                    match item.variant_id.as_ref().map(|id| product.get_variant(id)) {
+                        Some(None) => (), // variant was removed from the product
+                        Some(variant) => items.push(get_cart_item(
+                            product,
+                            variant,
+                            bundle_components,
+                            pricing,
+                            item.quantity,
+                        )),
+                        None => items.push(get_cart_item(
+                            product,
+                            None,
+                            bundle_components,
+                            pricing,
+                            item.quantity,
+                        )),
+                    }
                 }
             }
             cart.set_items(items);
@@ -389,43 +832,25 @@ impl CartAgent for CartAgentImpl {
         }
     }
 
-    async fn add_item(&mut self, product_id: String, quantity: u32) -> Result<(), AddItemError> {
-        let state = self.get_state();
-
-        println!(
-            "Adding item with product {} to the cart of user {}",
-            product_id, state.user_id
-        );
-
-        let updated = state.update_item_quantity(product_id.clone(), quantity, true);
-
-        if !updated {
-            let product_client = ProductAgentClient::get(product_id.clone());
-            let pricing_client = PricingAgentClient::get(product_id.clone());
+    // Ids of orders created from the cart, without refreshing the cart
+    fn get_order_ids(&self) -> Vec<String> {
+        self.state
+            .as_ref()
+            .map_or(vec![], |cart| cart.previous_order_ids.clone())
+    }
 
-            let (product, pricing) = join(
-                product_client.get_product(),
-                pricing_client.get_price(state.currency.clone(), PRICING_ZONE_DEFAULT.to_string()),
-            )
-            .await;
+    async fn add_item(&mut self, product_id: String, quantity: u32) -> Result<(), AddItemError> {
+        self.add_cart_item(product_id, None, quantity).await
+    }
 
-            match (product, pricing) {
-                (Some(product), Some(pricing)) => {
-                    state.add_item(get_cart_item(product, pricing, quantity));
-                }
-                (None, _) => {
-                    return Err(AddItemError::ProductNotFound(ProductNotFoundError::new(
-                        product_id,
-                    )));
-                }
-                _ => {
-                    return Err(AddItemError::PricingNotFound(PricingNotFoundError::new(
-                        product_id,
-                    )))
-                }
-            }
-        }
-        Ok(())
+    async fn add_variant_item(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+        quantity: u32,
+    ) -> Result<(), AddItemError> {
+        self.add_cart_item(product_id, Some(variant_id), quantity)
+            .await
     }
 
     async fn checkout(&mut self) -> Result<OrderConfirmation, CheckoutError> {
@@ -435,10 +860,20 @@ impl CartAgent for CartAgentImpl {
 
         create_order(order_id.clone(), state.clone()).await?;
 
+        let mut product_ids: Vec<String> =
+            state.items.iter().map(|i| i.product_id.clone()).collect();
+        product_ids.sort();
+        product_ids.dedup();
+
         state.order_created(order_id.clone());
 
         ShoppingAssistantAgentClient::get(state.user_id.clone()).trigger_recommend_items();
 
+        for product_id in product_ids.iter() {
+            ProductAffinityAgentClient::get(product_id.clone())
+                .trigger_record_order(order_id.clone(), product_ids.clone());
+        }
+
         Ok(OrderConfirmation { order_id })
     }
 
@@ -469,20 +904,15 @@ impl CartAgent for CartAgentImpl {
     }
 
     fn remove_item(&mut self, product_id: String) -> Result<(), RemoveItemError> {
-        self.with_state(|state| {
-            println!(
-                "Removing item with product {} from the cart of user {}",
-                product_id, state.user_id
-            );
+        self.remove_cart_item(product_id, None)
+    }
 
-            if state.remove_item(product_id.clone()) {
-                Ok(())
-            } else {
-                Err(RemoveItemError::ItemNotFound(ItemNotFoundError::new(
-                    product_id,
-                )))
-            }
-        })
+    fn remove_variant_item(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+    ) -> Result<(), RemoveItemError> {
+        self.remove_cart_item(product_id, Some(variant_id))
     }
 
     fn update_billing_address(&mut self, address: Address) -> Result<(), UpdateAddressError> {
@@ -497,27 +927,23 @@ impl CartAgent for CartAgentImpl {
         })
     }
 
-    fn update_item_quantity(
+    async fn update_item_quantity(
         &mut self,
         product_id: String,
         quantity: u32,
     ) -> Result<(), UpdateItemQuantityError> {
-        self.with_state(|state| {
-            println!(
-                "Updating quantity of item with product {} to {} in the cart of user {}",
-                product_id, quantity, state.user_id
-            );
-
-            let updated = state.update_item_quantity(product_id.clone(), quantity, false);
+        self.update_cart_item_quantity(product_id, None, quantity)
+            .await
+    }
 
-            if updated {
-                Ok(())
-            } else {
-                Err(UpdateItemQuantityError::ItemNotFound(
-                    ItemNotFoundError::new(product_id),
-                ))
-            }
-        })
+    async fn update_variant_item_quantity(
+        &mut self,
+        product_id: String,
+        variant_id: String,
+        quantity: u32,
+    ) -> Result<(), UpdateItemQuantityError> {
+        self.update_cart_item_quantity(product_id, Some(variant_id), quantity)
+            .await
     }
 
     fn update_shipping_address(&mut self, address: Address) -> Result<(), UpdateAddressError> {
@@ -0,0 +1,340 @@
+use crate::common::{CURRENCY_DEFAULT, PRICING_ZONE_DEFAULT};
+use crate::pricing_query::get_effective_prices;
+use crate::product::Product;
+use crate::product_search::{get_all_products, get_component_id};
+use crate::storage::{delete_object, read_object, write_object};
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use serde::Serialize;
+
+pub const CATALOG_EXPORT_CONTAINER: &str = "catalog-export";
+
+pub const CATALOG_FEED_BASE_URL_DEFAULT: &str = "http://localhost:3000";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct CatalogExportItem {
+    product_id: String,
+    name: String,
+    brand: String,
+    description: String,
+    tags: Vec<String>,
+    status: String,
+    category_ids: Vec<String>,
+    price: Option<f32>,
+    currency: String,
+    zone: String,
+}
+
+impl CatalogExportItem {
+    fn new(product: Product, price: Option<f32>, currency: &str, zone: &str) -> Self {
+        Self {
+            product_id: product.product_id,
+            name: product.name,
+            brand: product.brand,
+            description: product.description,
+            tags: product.tags,
+            status: product.status.as_str().to_string(),
+            category_ids: product.category_ids,
+            price,
+            currency: currency.to_string(),
+            zone: zone.to_string(),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        self.status == "active" && self.price.is_some()
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct CatalogExportFile {
+    pub file_name: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+#[derive(Schema, Clone)]
+pub struct CatalogExport {
+    pub export_id: String,
+    pub currency: String,
+    pub zone: String,
+    pub product_count: u32,
+    pub files: Vec<CatalogExportFile>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl CatalogExport {
+    fn get_object_name(&self, file_name: &str) -> String {
+        format!("{}/{}", self.export_id, file_name)
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct CatalogExportContent {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Schema, Clone)]
+pub struct ExportFailedError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum ExportCatalogError {
+    ExportFailed(ExportFailedError),
+}
+
+fn get_feed_base_url() -> String {
+    std::env::var("CATALOG_FEED_BASE_URL")
+        .unwrap_or(CATALOG_FEED_BASE_URL_DEFAULT.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn get_csv(items: &[CatalogExportItem]) -> String {
+    let mut lines = vec![
+        "product-id,name,brand,description,tags,status,category-ids,price,currency,zone"
+            .to_string(),
+    ];
+
+    for item in items {
+        let values = [
+            item.product_id.clone(),
+            item.name.clone(),
+            item.brand.clone(),
+            item.description.clone(),
+            item.tags.join("|"),
+            item.status.clone(),
+            item.category_ids.join("|"),
+            item.price.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+            item.currency.clone(),
+            item.zone.clone(),
+        ];
+        lines.push(
+            values
+                .iter()
+                .map(|v| csv_escape(v))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+
+    lines.join("\n")
+}
+
+// One product per line, in a shape accepted by the catalog import
+fn get_json_lines(items: &[CatalogExportItem]) -> Result<String, String> {
+    let lines = items
+        .iter()
+        .map(|item| serde_json::to_string(item).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(lines.join("\n"))
+}
+
+// Merchant feed (RSS 2.0 with the `g:` namespace), with available products only
+fn get_merchant_feed(items: &[CatalogExportItem], base_url: &str) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<rss version=\"2.0\" xmlns:g=\"http://base.google.com/ns/1.0\">\n");
+    xml.push_str("<channel>\n");
+    xml.push_str("<title>Golem Shopping</title>\n");
+    xml.push_str(&format!("<link>{}</link>\n", xml_escape(base_url)));
+    xml.push_str("<description>Golem Shopping product feed</description>\n");
+
+    for item in items.iter().filter(|item| item.is_available()) {
+        xml.push_str("<item>\n");
+        xml.push_str(&format!("<g:id>{}</g:id>\n", xml_escape(&item.product_id)));
+        xml.push_str(&format!("<g:title>{}</g:title>\n", xml_escape(&item.name)));
+        xml.push_str(&format!(
+            "<g:description>{}</g:description>\n",
+            xml_escape(&item.description)
+        ));
+        xml.push_str(&format!(
+            "<g:link>{}/products/{}</g:link>\n",
+            xml_escape(base_url),
+            xml_escape(&item.product_id)
+        ));
+        xml.push_str(&format!("<g:brand>{}</g:brand>\n", xml_escape(&item.brand)));
+        xml.push_str("<g:condition>new</g:condition>\n");
+        xml.push_str("<g:availability>in_stock</g:availability>\n");
+        if let Some(price) = item.price {
+            xml.push_str(&format!(
+                "<g:price>{:.2} {}</g:price>\n",
+                price,
+                xml_escape(&item.currency)
+            ));
+        }
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n");
+    xml.push_str("</rss>\n");
+    xml
+}
+
+async fn get_export_items(currency: &str, zone: &str) -> Result<Vec<CatalogExportItem>, String> {
+    let component_id = get_component_id().ok_or("Component not found".to_string())?;
+
+    // Products with any status are exported, products not sellable in the zone are excluded
+    let mut products =
+        get_all_products(component_id, None, |p| p.availability.is_zone_allowed(zone)).await;
+    products.sort_by(|a, b| a.product_id.cmp(&b.product_id));
+
+    let prices = get_effective_prices(
+        products.iter().map(|p| p.product_id.clone()).collect(),
+        currency.to_string(),
+        zone.to_string(),
+    )
+    .await;
+
+    Ok(products
+        .into_iter()
+        .map(|product| {
+            let price = prices.get(&product.product_id).map(|p| p.price);
+            CatalogExportItem::new(product, price, currency, zone)
+        })
+        .collect())
+}
+
+#[agent_definition]
+trait CatalogExportAgent {
+    fn new() -> Self;
+
+    fn get_export(&self) -> Option<CatalogExport>;
+
+    fn get_export_file(&self, file_name: String) -> Option<CatalogExportContent>;
+
+    async fn export(
+        &mut self,
+        currency: Option<String>,
+        zone: Option<String>,
+    ) -> Result<CatalogExport, ExportCatalogError>;
+}
+
+struct CatalogExportAgentImpl {
+    state: Option<CatalogExport>,
+}
+
+#[agent_implementation]
+impl CatalogExportAgent for CatalogExportAgentImpl {
+    fn new() -> Self {
+        CatalogExportAgentImpl { state: None }
+    }
+
+    fn get_export(&self) -> Option<CatalogExport> {
+        self.state.clone()
+    }
+
+    fn get_export_file(&self, file_name: String) -> Option<CatalogExportContent> {
+        let export = self.state.as_ref()?;
+        let file = export.files.iter().find(|f| f.file_name == file_name)?;
+
+        match read_object(
+            CATALOG_EXPORT_CONTAINER,
+            &export.get_object_name(&file_name),
+        ) {
+            Ok(data) => Some(CatalogExportContent {
+                file_name,
+                content_type: file.content_type.clone(),
+                data: String::from_utf8_lossy(&data).to_string(),
+                created_at: export.created_at,
+            }),
+            Err(e) => {
+                println!("Reading catalog export file {} failed: {}", file_name, e);
+                None
+            }
+        }
+    }
+
+    // Currency and pricing zone default to USD and global, if not set
+    async fn export(
+        &mut self,
+        currency: Option<String>,
+        zone: Option<String>,
+    ) -> Result<CatalogExport, ExportCatalogError> {
+        let currency = currency.unwrap_or(CURRENCY_DEFAULT.to_string());
+        let zone = zone.unwrap_or(PRICING_ZONE_DEFAULT.to_string());
+        println!("Exporting catalog - currency: {} zone: {}", currency, zone);
+
+        let to_error =
+            |message: String| ExportCatalogError::ExportFailed(ExportFailedError { message });
+
+        let items = get_export_items(&currency, &zone).await.map_err(to_error)?;
+
+        let mut export = CatalogExport {
+            export_id: uuid::Uuid::new_v4().to_string(),
+            currency,
+            zone,
+            product_count: items.len() as u32,
+            files: vec![],
+            created_at: chrono::Utc::now(),
+        };
+
+        let contents = [
+            ("catalog.csv", "text/csv", get_csv(&items)),
+            (
+                "catalog.jsonl",
+                "application/jsonl",
+                get_json_lines(&items).map_err(to_error)?,
+            ),
+            (
+                "feed.xml",
+                "application/xml",
+                get_merchant_feed(&items, &get_feed_base_url()),
+            ),
+        ];
+
+        for (file_name, content_type, data) in contents {
+            write_object(
+                CATALOG_EXPORT_CONTAINER,
+                &export.get_object_name(file_name),
+                data.as_bytes(),
+            )
+            .map_err(to_error)?;
+
+            export.files.push(CatalogExportFile {
+                file_name: file_name.to_string(),
+                content_type: content_type.to_string(),
+                size: data.len() as u64,
+            });
+        }
+
+        // Only the latest export is kept
+        if let Some(previous) = self.state.replace(export.clone()) {
+            for file in previous.files.iter() {
+                if let Err(e) = delete_object(
+                    CATALOG_EXPORT_CONTAINER,
+                    &previous.get_object_name(&file.file_name),
+                ) {
+                    println!(
+                        "Deleting catalog export file {} failed: {}",
+                        file.file_name, e
+                    );
+                }
+            }
+        }
+
+        Ok(export)
+    }
+}
@@ -0,0 +1,274 @@
+use crate::common::{ImportFormat, ImportRecord};
+use crate::import_job::{
+    get_import_rows, ImportJob, ImportRow, ImportRowStatus, ImportStatus, LoadImportError,
+    RunImportError,
+};
+use crate::product::{ProductAgentClient, PublishProductError, UpdateProduct, UpdateProductError};
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct CatalogImportBody {
+    product_id: Option<String>,
+    name: String,
+    brand: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Schema, Clone)]
+pub struct CatalogImportRow {
+    pub row_number: u32,
+    pub product_id: String,
+    pub name: String,
+    pub brand: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub status: ImportRowStatus,
+    pub errors: Vec<String>,
+}
+
+impl CatalogImportRow {
+    fn invalid(row_number: u32, product_id: String, errors: Vec<String>) -> Self {
+        Self {
+            row_number,
+            product_id,
+            name: String::new(),
+            brand: String::new(),
+            description: String::new(),
+            tags: vec![],
+            status: ImportRowStatus::Invalid,
+            errors,
+        }
+    }
+}
+
+impl ImportRow for CatalogImportRow {
+    fn product_id(&self) -> &str {
+        &self.product_id
+    }
+
+    fn status(&self) -> ImportRowStatus {
+        self.status
+    }
+
+    fn set_status(&mut self, status: ImportRowStatus) {
+        self.status = status;
+    }
+
+    fn errors_mut(&mut self) -> &mut Vec<String> {
+        &mut self.errors
+    }
+}
+
+fn get_report(state: &ImportJob<CatalogImportRow>) -> CatalogImportReport {
+    CatalogImportReport {
+        import_id: state.import_id.clone(),
+        status: state.status,
+        total: state.rows.len() as u32,
+        invalid: state.count(ImportRowStatus::Invalid),
+        pending: state.count(ImportRowStatus::Pending),
+        created: state.count(ImportRowStatus::Created),
+        updated: state.count(ImportRowStatus::Updated),
+        published: state.count(ImportRowStatus::Published),
+        failed: state.count(ImportRowStatus::Failed),
+        rows: state.rows.clone(),
+        updated_at: state.updated_at,
+    }
+}
+
+// Created products are drafts, published rows are created products which were published
+#[derive(Schema, Clone)]
+pub struct CatalogImportReport {
+    pub import_id: String,
+    pub status: ImportStatus,
+    pub total: u32,
+    pub invalid: u32,
+    pub pending: u32,
+    pub created: u32,
+    pub updated: u32,
+    pub published: u32,
+    pub failed: u32,
+    pub rows: Vec<CatalogImportRow>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn get_import_row(record: ImportRecord) -> CatalogImportRow {
+    if let Some(error) = record.error {
+        return CatalogImportRow::invalid(record.row_number, String::new(), vec![error]);
+    }
+
+    let body = match serde_json::from_str::<CatalogImportBody>(&record.body) {
+        Ok(body) => body,
+        Err(e) => {
+            return CatalogImportRow::invalid(
+                record.row_number,
+                record.id.unwrap_or_default(),
+                vec![format!("Invalid body: {e}")],
+            )
+        }
+    };
+
+    let mut errors = Vec::new();
+
+    let product_id = match (record.id, body.product_id) {
+        (Some(id), Some(body_id)) if id != body_id => {
+            errors.push(format!(
+                "Product id '{id}' does not match body product id '{body_id}'"
+            ));
+            id
+        }
+        (Some(id), _) => id,
+        (None, Some(body_id)) => body_id,
+        (None, None) => String::new(),
+    };
+
+    if product_id.trim().is_empty() {
+        errors.push("Empty product id".to_string());
+    }
+    if body.name.trim().is_empty() {
+        errors.push("Empty name".to_string());
+    }
+    if body.brand.trim().is_empty() {
+        errors.push("Empty brand".to_string());
+    }
+    if body.tags.iter().any(|tag| tag.trim().is_empty()) {
+        errors.push("Empty tag".to_string());
+    }
+
+    if errors.is_empty() {
+        CatalogImportRow {
+            row_number: record.row_number,
+            product_id,
+            name: body.name,
+            brand: body.brand,
+            description: body.description,
+            tags: body.tags,
+            status: ImportRowStatus::Pending,
+            errors,
+        }
+    } else {
+        CatalogImportRow::invalid(record.row_number, product_id, errors)
+    }
+}
+
+fn get_update_error_message(error: UpdateProductError) -> String {
+    match error {
+        UpdateProductError::ProductNotFound(e) => e.message,
+        UpdateProductError::AttributeNotValid(e) => e.message,
+    }
+}
+
+// Create the product, or update it if it already exists
+async fn apply_row(row: CatalogImportRow) -> Result<ImportRowStatus, String> {
+    let mut product_client = ProductAgentClient::get(row.product_id.clone());
+
+    if product_client.get_product().await.is_some() {
+        product_client
+            .update_product(UpdateProduct {
+                name: Some(row.name),
+                brand: Some(row.brand),
+                description: Some(row.description),
+                tags: Some(row.tags),
+            })
+            .await
+            .map_err(get_update_error_message)?;
+
+        Ok(ImportRowStatus::Updated)
+    } else {
+        product_client
+            .initialize_product(row.name, row.brand, row.description, row.tags)
+            .await
+            .map_err(get_update_error_message)?;
+
+        Ok(ImportRowStatus::Created)
+    }
+}
+
+async fn publish_row(row: CatalogImportRow) -> Result<ImportRowStatus, String> {
+    ProductAgentClient::get(row.product_id)
+        .publish_product()
+        .await
+        .map(|_| ImportRowStatus::Published)
+        .map_err(|error| match error {
+            PublishProductError::ProductNotFound(e) => e.message,
+            PublishProductError::ActionNotAllowed(e) => e.message,
+        })
+}
+
+#[agent_definition]
+trait CatalogImportAgent {
+    fn new(id: String) -> Self;
+
+    fn get_import(&self) -> Option<CatalogImportReport>;
+
+    fn load(
+        &mut self,
+        data: String,
+        format: ImportFormat,
+        has_header: bool,
+    ) -> Result<CatalogImportReport, LoadImportError>;
+
+    async fn apply(&mut self) -> Result<CatalogImportReport, RunImportError>;
+
+    async fn publish(&mut self) -> Result<CatalogImportReport, RunImportError>;
+}
+
+struct CatalogImportAgentImpl {
+    _id: String,
+    state: Option<ImportJob<CatalogImportRow>>,
+}
+
+impl CatalogImportAgentImpl {
+    fn get_state(&mut self) -> &mut ImportJob<CatalogImportRow> {
+        self.state.get_or_insert(ImportJob::new(self._id.clone()))
+    }
+}
+
+#[agent_implementation]
+impl CatalogImportAgent for CatalogImportAgentImpl {
+    fn new(id: String) -> Self {
+        CatalogImportAgentImpl {
+            _id: id,
+            state: None,
+        }
+    }
+
+    fn get_import(&self) -> Option<CatalogImportReport> {
+        self.state.as_ref().map(get_report)
+    }
+
+    fn load(
+        &mut self,
+        data: String,
+        format: ImportFormat,
+        has_header: bool,
+    ) -> Result<CatalogImportReport, LoadImportError> {
+        let state = self.get_state();
+        println!("Loading catalog import {}", state.import_id);
+
+        state.load(get_import_rows(&data, format, has_header, get_import_row))?;
+        Ok(get_report(state))
+    }
+
+    async fn apply(&mut self) -> Result<CatalogImportReport, RunImportError> {
+        let state = self.get_state();
+        println!("Applying catalog import {}", state.import_id);
+
+        state.apply(apply_row).await?;
+        Ok(get_report(state))
+    }
+
+    async fn publish(&mut self) -> Result<CatalogImportReport, RunImportError> {
+        let state = self.get_state();
+        println!("Publishing catalog import {}", state.import_id);
+
+        state
+            .update_applied_rows(|row| row.status == ImportRowStatus::Created, publish_row)
+            .await?;
+        Ok(get_report(state))
+    }
+}
@@ -0,0 +1,343 @@
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::HashSet;
+
+#[derive(Schema, Clone)]
+pub struct Category {
+    pub category_id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Flattened tree node, `path` contains the category ids from the root down to the node itself
+#[derive(Schema, Clone)]
+pub struct CategoryTreeItem {
+    pub category_id: String,
+    pub name: String,
+    pub parent_id: Option<String>,
+    pub path: Vec<String>,
+    pub depth: u32,
+}
+
+#[derive(Schema, Clone)]
+pub struct CreateCategory {
+    pub name: String,
+    pub parent_id: Option<String>,
+}
+
+#[derive(Schema, Clone)]
+pub struct Taxonomy {
+    pub categories: Vec<Category>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Taxonomy {
+    fn new() -> Self {
+        Self {
+            categories: vec![],
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn get_category(&self, category_id: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|c| c.category_id == category_id)
+    }
+
+    fn get_category_mut(&mut self, category_id: &str) -> Option<&mut Category> {
+        self.categories
+            .iter_mut()
+            .find(|c| c.category_id == category_id)
+    }
+
+    fn get_children(&self, parent_id: Option<&str>) -> Vec<&Category> {
+        let mut children: Vec<&Category> = self
+            .categories
+            .iter()
+            .filter(|c| c.parent_id.as_deref() == parent_id)
+            .collect();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        children
+    }
+
+    // Ids of the category and all its descendants
+    fn get_descendant_ids(&self, category_id: &str) -> HashSet<String> {
+        let mut result: HashSet<String> = HashSet::new();
+
+        if self.get_category(category_id).is_some() {
+            let mut pending = vec![category_id.to_string()];
+
+            while let Some(id) = pending.pop() {
+                if result.insert(id.clone()) {
+                    pending.extend(
+                        self.get_children(Some(&id))
+                            .into_iter()
+                            .map(|c| c.category_id.clone()),
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    // Categories from the root down to the given category
+    fn get_path(&self, category_id: &str) -> Vec<Category> {
+        let mut path = Vec::new();
+        let mut current = self.get_category(category_id);
+
+        while let Some(category) = current {
+            path.push(category.clone());
+            current = category
+                .parent_id
+                .as_deref()
+                .and_then(|id| self.get_category(id));
+        }
+
+        path.reverse();
+        path
+    }
+
+    fn get_tree(&self) -> Vec<CategoryTreeItem> {
+        fn visit(
+            taxonomy: &Taxonomy,
+            parent_id: Option<&str>,
+            path: &[String],
+            result: &mut Vec<CategoryTreeItem>,
+        ) {
+            for category in taxonomy.get_children(parent_id) {
+                let mut category_path = path.to_vec();
+                category_path.push(category.category_id.clone());
+
+                result.push(CategoryTreeItem {
+                    category_id: category.category_id.clone(),
+                    name: category.name.clone(),
+                    parent_id: category.parent_id.clone(),
+                    path: category_path.clone(),
+                    depth: path.len() as u32,
+                });
+
+                visit(
+                    taxonomy,
+                    Some(&category.category_id),
+                    &category_path,
+                    result,
+                );
+            }
+        }
+
+        let mut result = Vec::new();
+        visit(self, None, &[], &mut result);
+        result
+    }
+
+    fn add_category(&mut self, category: Category) {
+        self.categories.push(category);
+        self.updated_at = chrono::Utc::now();
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct CategoryNotFoundError {
+    pub message: String,
+    pub category_id: String,
+}
+
+impl CategoryNotFoundError {
+    pub fn new(category_id: String) -> CategoryNotFoundError {
+        CategoryNotFoundError {
+            message: "Category not found".to_string(),
+            category_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct CategoryAlreadyExistsError {
+    pub message: String,
+    pub category_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct MoveNotAllowedError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum CreateCategoryError {
+    CategoryAlreadyExists(CategoryAlreadyExistsError),
+    ParentNotFound(CategoryNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RenameCategoryError {
+    CategoryNotFound(CategoryNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub enum MoveCategoryError {
+    CategoryNotFound(CategoryNotFoundError),
+    ParentNotFound(CategoryNotFoundError),
+    MoveNotAllowed(MoveNotAllowedError),
+}
+
+#[agent_definition]
+trait CategoryAgent {
+    fn new() -> Self;
+
+    fn get_category(&self, category_id: String) -> Option<Category>;
+
+    fn get_categories(&self) -> Vec<CategoryTreeItem>;
+
+    fn get_category_path(&self, category_id: String) -> Vec<Category>;
+
+    fn get_descendant_ids(&self, category_id: String) -> Vec<String>;
+
+    fn create_category(
+        &mut self,
+        category_id: String,
+        data: CreateCategory,
+    ) -> Result<(), CreateCategoryError>;
+
+    fn rename_category(
+        &mut self,
+        category_id: String,
+        name: String,
+    ) -> Result<(), RenameCategoryError>;
+
+    fn move_category(
+        &mut self,
+        category_id: String,
+        parent_id: Option<String>,
+    ) -> Result<(), MoveCategoryError>;
+}
+
+struct CategoryAgentImpl {
+    state: Taxonomy,
+}
+
+#[agent_implementation]
+impl CategoryAgent for CategoryAgentImpl {
+    fn new() -> Self {
+        CategoryAgentImpl {
+            state: Taxonomy::new(),
+        }
+    }
+
+    fn get_category(&self, category_id: String) -> Option<Category> {
+        self.state.get_category(&category_id).cloned()
+    }
+
+    fn get_categories(&self) -> Vec<CategoryTreeItem> {
+        self.state.get_tree()
+    }
+
+    fn get_category_path(&self, category_id: String) -> Vec<Category> {
+        self.state.get_path(&category_id)
+    }
+
+    fn get_descendant_ids(&self, category_id: String) -> Vec<String> {
+        self.state
+            .get_descendant_ids(&category_id)
+            .into_iter()
+            .collect()
+    }
+
+    fn create_category(
+        &mut self,
+        category_id: String,
+        data: CreateCategory,
+    ) -> Result<(), CreateCategoryError> {
+        println!("Creating category {}", category_id);
+
+        if self.state.get_category(&category_id).is_some() {
+            return Err(CreateCategoryError::CategoryAlreadyExists(
+                CategoryAlreadyExistsError {
+                    message: "Category already exists".to_string(),
+                    category_id,
+                },
+            ));
+        }
+
+        if let Some(parent_id) = data.parent_id.clone() {
+            if self.state.get_category(&parent_id).is_none() {
+                return Err(CreateCategoryError::ParentNotFound(
+                    CategoryNotFoundError::new(parent_id),
+                ));
+            }
+        }
+
+        let now = chrono::Utc::now();
+        self.state.add_category(Category {
+            category_id,
+            name: data.name,
+            parent_id: data.parent_id,
+            created_at: now,
+            updated_at: now,
+        });
+        Ok(())
+    }
+
+    fn rename_category(
+        &mut self,
+        category_id: String,
+        name: String,
+    ) -> Result<(), RenameCategoryError> {
+        println!("Renaming category {} to {}", category_id, name);
+
+        match self.state.get_category_mut(&category_id) {
+            Some(category) => {
+                let now = chrono::Utc::now();
+                category.name = name;
+                category.updated_at = now;
+                self.state.updated_at = now;
+                Ok(())
+            }
+            None => Err(RenameCategoryError::CategoryNotFound(
+                CategoryNotFoundError::new(category_id),
+            )),
+        }
+    }
+
+    fn move_category(
+        &mut self,
+        category_id: String,
+        parent_id: Option<String>,
+    ) -> Result<(), MoveCategoryError> {
+        println!("Moving category {} to parent {:?}", category_id, parent_id);
+
+        if self.state.get_category(&category_id).is_none() {
+            return Err(MoveCategoryError::CategoryNotFound(
+                CategoryNotFoundError::new(category_id),
+            ));
+        }
+
+        if let Some(parent_id) = parent_id.clone() {
+            if self.state.get_category(&parent_id).is_none() {
+                return Err(MoveCategoryError::ParentNotFound(
+                    CategoryNotFoundError::new(parent_id),
+                ));
+            }
+            if self
+                .state
+                .get_descendant_ids(&category_id)
+                .contains(&parent_id)
+            {
+                return Err(MoveCategoryError::MoveNotAllowed(MoveNotAllowedError {
+                    message: "Category can not be moved under itself or its descendants"
+                        .to_string(),
+                }));
+            }
+        }
+
+        let now = chrono::Utc::now();
+        if let Some(category) = self.state.get_category_mut(&category_id) {
+            category.parent_id = parent_id;
+            category.updated_at = now;
+        }
+        self.state.updated_at = now;
+        Ok(())
+    }
+}
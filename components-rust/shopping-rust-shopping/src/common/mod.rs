@@ -1,7 +1,10 @@
 use golem_rust::Schema;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 pub const CURRENCY_DEFAULT: &str = "USD";
 pub const PRICING_ZONE_DEFAULT: &str = "global";
+pub const LOCALE_DEFAULT: &str = "en";
 
 #[derive(Schema, Clone)]
 pub struct Address {
@@ -13,3 +16,142 @@ pub struct Address {
     pub name: Option<String>,
     pub phone_number: Option<String>,
 }
+
+// Lowercase text with diacritics removed, e.g. `Crème Brûlée` to `creme brulee`
+pub fn fold_text(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// Parse comma separated ids, dropping empty entries
+pub fn parse_ids(ids: &str) -> Vec<String> {
+    ids.split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+// Normalize locale to `language` or `language-REGION` form, e.g. `de_at` to `de-AT`
+pub fn normalize_locale(locale: &str) -> Option<String> {
+    let locale = locale.trim().replace('_', "-");
+    let mut parts = locale.split('-');
+
+    let language = parts.next()?.to_lowercase();
+    if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    match (parts.next(), parts.next()) {
+        (None, _) => Some(language),
+        (Some(region), None)
+            if region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            Some(format!("{}-{}", language, region.to_uppercase()))
+        }
+        _ => None,
+    }
+}
+
+// Locale fallback chain, e.g. `de-AT` -> `de` -> `en`
+pub fn get_locale_chain(locale: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+
+    if let Some(locale) = normalize_locale(locale) {
+        if let Some((language, _)) = locale.split_once('-') {
+            chain.push(locale.clone());
+            chain.push(language.to_string());
+        } else {
+            chain.push(locale);
+        }
+    }
+
+    if !chain.iter().any(|l| l == LOCALE_DEFAULT) {
+        chain.push(LOCALE_DEFAULT.to_string());
+    }
+
+    chain
+}
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    JsonLines,
+}
+
+// Raw import record, body is a JSON document in the same shape as the HTTP API requests,
+// `error` is set if the record could not be read
+#[derive(Clone, Debug)]
+pub struct ImportRecord {
+    pub row_number: u32,
+    pub id: Option<String>,
+    pub body: String,
+    pub error: Option<String>,
+}
+
+impl ImportRecord {
+    fn invalid(row_number: u32, error: String) -> Self {
+        Self {
+            row_number,
+            id: None,
+            body: String::new(),
+            error: Some(error),
+        }
+    }
+}
+
+// Split import data into records
+//
+// CSV data follows `data/fixtures/*.csv`: `id,body` columns with the JSON body quoted with `'`,
+// the first row is skipped if `has_header` is set,
+// JSON lines data contains one JSON body per line
+pub fn get_import_records(data: &str, format: ImportFormat, has_header: bool) -> Vec<ImportRecord> {
+    match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(has_header)
+                .quote(b'\'')
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(data.as_bytes());
+
+            reader
+                .records()
+                .map(|record| match record {
+                    Ok(record) => {
+                        let row_number = record.position().map_or(0, |p| p.line() as u32);
+                        match (record.get(0), record.get(1), record.len()) {
+                            (Some(id), Some(body), 2) => ImportRecord {
+                                row_number,
+                                id: Some(id.to_string()),
+                                body: body.to_string(),
+                                error: None,
+                            },
+                            (_, _, len) => ImportRecord::invalid(
+                                row_number,
+                                format!("Expected 2 columns, found {}", len),
+                            ),
+                        }
+                    }
+                    Err(e) => ImportRecord::invalid(
+                        e.position().map_or(0, |p| p.line() as u32),
+                        format!("Invalid CSV: {}", e),
+                    ),
+                })
+                .collect()
+        }
+        ImportFormat::JsonLines => data
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index as u32 + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(row_number, line)| ImportRecord {
+                row_number,
+                id: None,
+                body: line.to_string(),
+                error: None,
+            })
+            .collect(),
+    }
+}
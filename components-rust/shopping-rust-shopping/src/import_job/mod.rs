@@ -0,0 +1,270 @@
+use crate::common::{get_import_records, ImportFormat, ImportRecord};
+use futures::future::join_all;
+use golem_rust::Schema;
+use std::collections::HashSet;
+use std::future::Future;
+
+pub const IMPORT_CONCURRENCY: usize = 10;
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ImportStatus {
+    New,
+    Loaded,
+    Applying,
+    Completed,
+}
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ImportRowStatus {
+    Invalid,
+    Pending,
+    Created,
+    Updated,
+    Failed,
+    Approved,
+    Published,
+}
+
+#[derive(Schema, Clone)]
+pub struct ActionNotAllowedError {
+    pub message: String,
+    pub status: ImportStatus,
+}
+
+impl ActionNotAllowedError {
+    fn new(status: ImportStatus) -> ActionNotAllowedError {
+        ActionNotAllowedError {
+            message: "Can not update import with status".to_string(),
+            status,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ImportNotLoadedError {
+    pub message: String,
+}
+
+impl ImportNotLoadedError {
+    fn new() -> ImportNotLoadedError {
+        ImportNotLoadedError {
+            message: "Import not loaded".to_string(),
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub enum LoadImportError {
+    ActionNotAllowed(ActionNotAllowedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RunImportError {
+    ImportNotLoaded(ImportNotLoadedError),
+    ActionNotAllowed(ActionNotAllowedError),
+}
+
+// Row of an import, rows are identified by the product id
+pub trait ImportRow: Clone {
+    fn product_id(&self) -> &str;
+
+    fn status(&self) -> ImportRowStatus;
+
+    fn set_status(&mut self, status: ImportRowStatus);
+
+    fn errors_mut(&mut self) -> &mut Vec<String>;
+
+    // Failed rows are retried
+    fn is_pending(&self) -> bool {
+        self.status() == ImportRowStatus::Pending || self.status() == ImportRowStatus::Failed
+    }
+
+    fn set_result(&mut self, result: Result<ImportRowStatus, String>) {
+        match result {
+            Ok(status) => {
+                self.set_status(status);
+                self.errors_mut().clear();
+            }
+            Err(e) => {
+                self.set_status(ImportRowStatus::Failed);
+                *self.errors_mut() = vec![e];
+            }
+        }
+    }
+}
+
+// Rows of the import data, rows with a product id of a previous valid row are invalid
+pub fn get_import_rows<R: ImportRow>(
+    data: &str,
+    format: ImportFormat,
+    has_header: bool,
+    get_import_row: impl Fn(ImportRecord) -> R,
+) -> Vec<R> {
+    let mut product_ids: HashSet<String> = HashSet::new();
+
+    get_import_records(data, format, has_header)
+        .into_iter()
+        .map(get_import_row)
+        .map(|mut row| {
+            if row.status() == ImportRowStatus::Pending
+                && !product_ids.insert(row.product_id().to_string())
+            {
+                row.set_status(ImportRowStatus::Invalid);
+                row.errors_mut().push("Duplicate product id".to_string());
+            }
+            row
+        })
+        .collect()
+}
+
+pub struct ImportJob<R: ImportRow> {
+    pub import_id: String,
+    pub status: ImportStatus,
+    pub rows: Vec<R>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<R: ImportRow> ImportJob<R> {
+    pub fn new(import_id: String) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            import_id,
+            status: ImportStatus::New,
+            rows: vec![],
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn set_status(&mut self, status: ImportStatus) {
+        self.status = status;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    pub fn count(&self, status: ImportRowStatus) -> u32 {
+        self.rows
+            .iter()
+            .filter(|row| row.status() == status)
+            .count() as u32
+    }
+
+    // Rows can be loaded again until the import is applied
+    pub fn load(&mut self, rows: Vec<R>) -> Result<(), LoadImportError> {
+        match self.status {
+            ImportStatus::New | ImportStatus::Loaded => {
+                self.rows = rows;
+                self.set_status(ImportStatus::Loaded);
+                Ok(())
+            }
+            status => Err(LoadImportError::ActionNotAllowed(
+                ActionNotAllowedError::new(status),
+            )),
+        }
+    }
+
+    // Update the selected rows with the results of `get`, rows are processed in chunks of bounded concurrency
+    async fn update_rows<T, Fut>(
+        &mut self,
+        is_selected: impl Fn(&R) -> bool,
+        get: impl Fn(R) -> Fut,
+        update: impl Fn(&mut R, T),
+    ) where
+        Fut: Future<Output = T>,
+    {
+        let selected: Vec<usize> = (0..self.rows.len())
+            .filter(|i| is_selected(&self.rows[*i]))
+            .collect();
+
+        for chunk in selected.chunks(IMPORT_CONCURRENCY) {
+            let results = join_all(chunk.iter().map(|i| get(self.rows[*i].clone()))).await;
+
+            for (i, result) in chunk.iter().zip(results) {
+                update(&mut self.rows[*i], result);
+            }
+        }
+
+        self.updated_at = chrono::Utc::now();
+    }
+
+    // Pending rows of a loaded import can be inspected before applying
+    pub async fn inspect<T, Fut>(
+        &mut self,
+        get: impl Fn(R) -> Fut,
+        update: impl Fn(&mut R, T),
+    ) -> Result<(), RunImportError>
+    where
+        Fut: Future<Output = T>,
+    {
+        match self.status {
+            ImportStatus::New => Err(RunImportError::ImportNotLoaded(ImportNotLoadedError::new())),
+            ImportStatus::Loaded => {
+                self.update_rows(R::is_pending, get, update).await;
+                Ok(())
+            }
+            status => Err(RunImportError::ActionNotAllowed(
+                ActionNotAllowedError::new(status),
+            )),
+        }
+    }
+
+    pub async fn apply<Fut>(&mut self, apply_row: impl Fn(R) -> Fut) -> Result<(), RunImportError>
+    where
+        Fut: Future<Output = Result<ImportRowStatus, String>>,
+    {
+        match self.status {
+            ImportStatus::New => {
+                return Err(RunImportError::ImportNotLoaded(ImportNotLoadedError::new()))
+            }
+            // Applying means a previous run was interrupted, continue with the rows not applied yet
+            ImportStatus::Loaded | ImportStatus::Applying => {
+                self.set_status(ImportStatus::Applying);
+            }
+            ImportStatus::Completed if self.count(ImportRowStatus::Failed) > 0 => {
+                self.set_status(ImportStatus::Applying);
+            }
+            status => {
+                return Err(RunImportError::ActionNotAllowed(
+                    ActionNotAllowedError::new(status),
+                ))
+            }
+        }
+
+        self.update_rows(R::is_pending, apply_row, |row, result| {
+            row.set_result(result)
+        })
+        .await;
+
+        self.set_status(ImportStatus::Completed);
+        Ok(())
+    }
+
+    // Applied rows of a completed import can be processed further, e.g. to approve the applied changes,
+    // failures are recorded as row errors, the row status is kept
+    pub async fn update_applied_rows<Fut>(
+        &mut self,
+        is_selected: impl Fn(&R) -> bool,
+        update_row: impl Fn(R) -> Fut,
+    ) -> Result<(), RunImportError>
+    where
+        Fut: Future<Output = Result<ImportRowStatus, String>>,
+    {
+        match self.status {
+            ImportStatus::New => Err(RunImportError::ImportNotLoaded(ImportNotLoadedError::new())),
+            ImportStatus::Completed => {
+                self.update_rows(is_selected, update_row, |row, result| match result {
+                    Ok(status) => {
+                        row.set_status(status);
+                        row.errors_mut().clear();
+                    }
+                    Err(e) => *row.errors_mut() = vec![e],
+                })
+                .await;
+                Ok(())
+            }
+            status => Err(RunImportError::ActionNotAllowed(
+                ActionNotAllowedError::new(status),
+            )),
+        }
+    }
+}
@@ -1,7 +1,22 @@
+pub mod attribute;
 pub mod cart;
+pub mod catalog_export;
+pub mod catalog_import;
+pub mod category;
 pub mod common;
+pub mod import_job;
+pub mod media;
 pub mod order;
 pub mod pricing;
+pub mod pricing_import;
+pub mod pricing_query;
 pub mod product;
+pub mod product_affinity;
+pub mod product_query;
+pub mod product_reviews;
 pub mod product_search;
+pub mod product_suggest;
+pub mod search_analytics;
+pub mod search_dictionary;
 pub mod shopping_assistant;
+pub mod storage;
@@ -0,0 +1,93 @@
+use crate::storage::{delete_object, read_object, write_object};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use golem_rust::Schema;
+
+pub const MEDIA_CONTAINER: &str = "product-media";
+
+pub const MEDIA_MAX_SIZE: usize = 5 * 1024 * 1024;
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum MediaKind {
+    Image,
+    Document,
+}
+
+// Media metadata, content is stored in the blobstore under `<product-id>/<media-id>`
+#[derive(Schema, Clone)]
+pub struct ProductMedia {
+    pub media_id: String,
+    pub kind: MediaKind,
+    pub content_type: String,
+    pub alt_text: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: u64,
+    pub primary: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Media upload, `data` is base64 encoded
+#[derive(Schema, Clone)]
+pub struct UploadMedia {
+    pub kind: MediaKind,
+    pub content_type: String,
+    pub alt_text: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub primary: bool,
+    pub data: String,
+}
+
+// Media content, `data` is base64 encoded
+#[derive(Schema, Clone)]
+pub struct MediaContent {
+    pub media_id: String,
+    pub content_type: String,
+    pub data: String,
+}
+
+pub fn get_object_name(product_id: &str, media_id: &str) -> String {
+    format!("{}/{}", product_id, media_id)
+}
+
+// Decode and validate upload data
+pub fn decode_media(data: &UploadMedia) -> Result<Vec<u8>, String> {
+    if data.kind == MediaKind::Image && !data.content_type.starts_with("image/") {
+        return Err(format!(
+            "Content type {} is not an image",
+            data.content_type
+        ));
+    }
+    if data.content_type.trim().is_empty() {
+        return Err("Content type is empty".to_string());
+    }
+
+    let bytes = BASE64_STANDARD
+        .decode(data.data.as_bytes())
+        .map_err(|e| format!("Data is not valid base64: {}", e))?;
+
+    if bytes.is_empty() {
+        return Err("Data is empty".to_string());
+    }
+    if bytes.len() > MEDIA_MAX_SIZE {
+        return Err(format!("Data exceeds max size of {} bytes", MEDIA_MAX_SIZE));
+    }
+
+    Ok(bytes)
+}
+
+pub fn encode_media(data: &[u8]) -> String {
+    BASE64_STANDARD.encode(data)
+}
+
+pub fn write_media(object_name: &str, data: &[u8]) -> Result<(), String> {
+    write_object(MEDIA_CONTAINER, object_name, data)
+}
+
+pub fn read_media(object_name: &str) -> Result<Vec<u8>, String> {
+    read_object(MEDIA_CONTAINER, object_name)
+}
+
+pub fn delete_media(object_name: &str) -> Result<(), String> {
+    delete_object(MEDIA_CONTAINER, object_name)
+}
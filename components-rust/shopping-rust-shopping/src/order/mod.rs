@@ -1,6 +1,9 @@
 use crate::common::{Address, CURRENCY_DEFAULT, PRICING_ZONE_DEFAULT};
 use crate::pricing::PricingAgentClient;
-use crate::product::ProductAgentClient;
+use crate::product::{
+    get_bundle_component_items, BundleComponentError, BundleComponentItem, ProductAgentClient,
+    SelectedOption,
+};
 use email_address::EmailAddress;
 use futures::future::join;
 use golem_rust::{agent_definition, agent_implementation, Schema};
@@ -39,6 +42,34 @@ impl Order {
         }
     }
 
+    fn get_fulfilment_items(&self) -> Vec<FulfilmentItem> {
+        self.items
+            .iter()
+            .flat_map(|item| {
+                if item.bundle_components.is_empty() {
+                    vec![FulfilmentItem {
+                        product_id: item.product_id.clone(),
+                        product_name: item.product_name.clone(),
+                        variant_id: item.variant_id.clone(),
+                        bundle_product_id: None,
+                        quantity: item.quantity,
+                    }]
+                } else {
+                    item.bundle_components
+                        .iter()
+                        .map(|component| FulfilmentItem {
+                            product_id: component.product_id.clone(),
+                            product_name: component.product_name.clone(),
+                            variant_id: None,
+                            bundle_product_id: Some(item.product_id.clone()),
+                            quantity: component.quantity * item.quantity,
+                        })
+                        .collect()
+                }
+            })
+            .collect()
+    }
+
     fn recalculate_total(&mut self) {
         self.total = get_total_price(self.items.clone());
         self.updated_at = chrono::Utc::now();
@@ -73,8 +104,9 @@ impl Order {
     fn update_item_quantity(&mut self, product_id: String, quantity: u32, add: bool) -> bool {
         let mut updated = false;
 
+        // Variant lines are not matched, they are only created from the cart
         for item in &mut self.items {
-            if item.product_id == product_id {
+            if item.product_id == product_id && item.variant_id.is_none() {
                 if add {
                     item.quantity += quantity;
                 } else {
@@ -108,10 +140,23 @@ pub struct OrderItem {
     pub product_id: String,
     pub product_name: String,
     pub product_brand: String,
+    pub variant_id: Option<String>,
+    pub options: Vec<SelectedOption>,
+    pub bundle_components: Vec<BundleComponentItem>,
     pub price: f32,
     pub quantity: u32,
 }
 
+// Product to fulfil, bundle lines are expanded to their components
+#[derive(Schema, Clone)]
+pub struct FulfilmentItem {
+    pub product_id: String,
+    pub product_name: String,
+    pub variant_id: Option<String>,
+    pub bundle_product_id: Option<String>,
+    pub quantity: u32,
+}
+
 #[derive(Schema, Clone, Copy, Eq, PartialEq)]
 pub enum OrderStatus {
     New,
@@ -190,6 +235,21 @@ impl ProductNotFoundError {
     }
 }
 
+#[derive(Schema, Clone)]
+pub struct ProductNotAvailableError {
+    pub message: String,
+    pub product_id: String,
+}
+
+impl ProductNotAvailableError {
+    fn new(product_id: String) -> ProductNotAvailableError {
+        ProductNotAvailableError {
+            message: "Product not available".to_string(),
+            product_id,
+        }
+    }
+}
+
 #[derive(Schema, Clone)]
 pub struct EmailNotValidError {
     pub message: String,
@@ -213,6 +273,7 @@ pub struct EmptyEmailError {
 #[derive(Schema, Clone)]
 pub enum AddItemError {
     ProductNotFound(ProductNotFoundError),
+    ProductNotAvailable(ProductNotAvailableError),
     PricingNotFound(PricingNotFoundError),
     ActionNotAllowed(ActionNotAllowedError),
 }
@@ -267,6 +328,7 @@ trait OrderAgent {
     fn new(id: String) -> Self;
     fn initialize_order(&mut self, data: CreateOrder) -> Result<(), InitOrderError>;
     fn get_order(&self) -> Option<Order>;
+    fn get_fulfilment_items(&self) -> Vec<FulfilmentItem>;
     async fn add_item(&mut self, product_id: String, quantity: u32) -> Result<(), AddItemError>;
     fn update_email(&mut self, email: String) -> Result<(), UpdateEmailError>;
     fn remove_item(&mut self, product_id: String) -> Result<(), RemoveItemError>;
@@ -310,6 +372,13 @@ impl OrderAgent for OrderAgentImpl {
         self.state.clone()
     }
 
+    fn get_fulfilment_items(&self) -> Vec<FulfilmentItem> {
+        self.state
+            .as_ref()
+            .map(|order| order.get_fulfilment_items())
+            .unwrap_or_default()
+    }
+
     fn initialize_order(&mut self, data: CreateOrder) -> Result<(), InitOrderError> {
         self.with_state(|state| {
             println!(
@@ -379,11 +448,37 @@ impl OrderAgent for OrderAgentImpl {
             )
             .await;
             match (product, pricing) {
+                (Some(product), _) if !product.is_available() => {
+                    return Err(AddItemError::ProductNotAvailable(
+                        ProductNotAvailableError::new(product_id),
+                    ));
+                }
                 (Some(product), Some(pricing)) => {
+                    let bundle_components = if product.is_bundle() {
+                        get_bundle_component_items(&product, PRICING_ZONE_DEFAULT, None)
+                            .await
+                            .map_err(|e| match e {
+                                BundleComponentError::ProductNotFound(id) => {
+                                    AddItemError::ProductNotFound(ProductNotFoundError::new(id))
+                                }
+                                BundleComponentError::ProductNotAvailable(id)
+                                | BundleComponentError::ProductNotSellable(id) => {
+                                    AddItemError::ProductNotAvailable(
+                                        ProductNotAvailableError::new(id),
+                                    )
+                                }
+                            })?
+                    } else {
+                        vec![]
+                    };
+
                     state.add_item(OrderItem {
                         product_id,
                         product_name: product.name,
                         product_brand: product.brand,
+                        variant_id: None,
+                        options: vec![],
+                        bundle_components,
                         price: pricing.price,
                         quantity,
                     });
@@ -1,5 +1,7 @@
+use crate::pricing_query::get_prices;
+use crate::product::ProductAgentClient;
 use golem_rust::{agent_definition, agent_implementation, Schema};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Schema, Clone)]
 pub struct Pricing {
@@ -7,25 +9,88 @@ pub struct Pricing {
     pub msrp_prices: Vec<PricingItem>,
     pub list_prices: Vec<PricingItem>,
     pub sale_prices: Vec<SalePricingItem>,
+    pub variant_prices: Vec<VariantPricing>,
+    pub draft: Option<PricingDraft>,
+    // Incremented on every change of the published prices
+    pub version: u64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl Pricing {
-    fn new(product_id: String) -> Self {
+    pub fn new(product_id: String) -> Self {
         let now = chrono::Utc::now();
         Self {
             product_id,
             msrp_prices: vec![],
             list_prices: vec![],
             sale_prices: vec![],
+            variant_prices: vec![],
+            draft: None,
+            version: 0,
             created_at: now,
             updated_at: now,
         }
     }
 
+    fn set_updated(&mut self) {
+        self.version += 1;
+        self.updated_at = chrono::Utc::now();
+    }
+
     fn get_price(&self, currency: String, zone: String) -> Option<PricingItem> {
-        get_price(currency, zone, self.clone())
+        get_price(
+            &currency,
+            &zone,
+            &self.msrp_prices,
+            &self.list_prices,
+            &self.sale_prices,
+        )
+    }
+
+    // Price with the regular price, if a sale price is active
+    fn get_effective_price(&self, currency: String, zone: String) -> Option<EffectivePricingItem> {
+        let regular_price =
+            get_regular_price(&currency, &zone, &self.msrp_prices, &self.list_prices);
+
+        match get_sale_price(&currency, &zone, &self.sale_prices) {
+            Some(sale_price) => Some(EffectivePricingItem {
+                price: sale_price.price,
+                currency,
+                zone,
+                regular_price: regular_price.map(|p| p.price),
+                on_sale: true,
+            }),
+            None => regular_price.map(|p| EffectivePricingItem {
+                price: p.price,
+                currency,
+                zone,
+                regular_price: None,
+                on_sale: false,
+            }),
+        }
+    }
+
+    // Variant price, falling back to the product price if the variant has no price for the currency and zone
+    fn get_variant_price(
+        &self,
+        variant_id: String,
+        currency: String,
+        zone: String,
+    ) -> Option<PricingItem> {
+        self.variant_prices
+            .iter()
+            .find(|v| v.variant_id == variant_id)
+            .and_then(|v| {
+                get_price(
+                    &currency,
+                    &zone,
+                    &v.msrp_prices,
+                    &v.list_prices,
+                    &v.sale_prices,
+                )
+            })
+            .or_else(|| self.get_price(currency, zone))
     }
 
     fn set_prices(
@@ -37,9 +102,206 @@ impl Pricing {
         self.msrp_prices = msrp_prices;
         self.list_prices = list_prices;
         self.sale_prices = sale_prices;
+        self.set_updated();
+    }
+
+    // Draft based on the current published prices, if there is no draft yet
+    fn get_draft(&mut self, author: String) -> &mut PricingDraft {
+        let draft = self.draft.get_or_insert(PricingDraft::new(
+            self.msrp_prices.clone(),
+            self.list_prices.clone(),
+            self.sale_prices.clone(),
+            self.variant_prices.clone(),
+            self.version,
+        ));
+        if !draft.editors.contains(&author) {
+            draft.editors.push(author.clone());
+        }
+        draft.updated_by = author;
+        draft
+    }
+
+    fn set_draft_prices(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
+    ) {
+        self.get_draft(author)
+            .set_prices(msrp_prices, list_prices, sale_prices);
+    }
+
+    fn update_draft_prices(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
+    ) {
+        self.get_draft(author)
+            .update_prices(msrp_prices, list_prices, sale_prices);
+    }
+
+    fn update_draft_variant_prices(
+        &mut self,
+        variant_id: String,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
+    ) {
+        self.get_draft(author).update_variant_prices(
+            variant_id,
+            msrp_prices,
+            list_prices,
+            sale_prices,
+        );
+    }
+
+    // Remove the variant prices in the draft, the draft is not created if the variant has no prices
+    fn remove_draft_variant_prices(&mut self, variant_id: String, author: String) -> bool {
+        let variant_prices = match self.draft.as_ref() {
+            Some(draft) => &draft.variant_prices,
+            None => &self.variant_prices,
+        };
+        let exist = variant_prices.iter().any(|v| v.variant_id == variant_id);
+
+        if exist {
+            self.get_draft(author).remove_variant_prices(variant_id);
+        }
+
+        exist
+    }
+
+    fn publish_draft(&mut self, draft: PricingDraft) {
+        self.msrp_prices = draft.msrp_prices;
+        self.list_prices = draft.list_prices;
+        self.sale_prices = draft.sale_prices;
+        self.variant_prices = draft.variant_prices;
+        self.draft = None;
+        self.set_updated();
+    }
+
+    pub fn get_diff(
+        &self,
+        msrp_prices: &[PricingItem],
+        list_prices: &[PricingItem],
+        sale_prices: &[SalePricingItem],
+        variant_prices: &[VariantPricing],
+    ) -> PricingDiff {
+        PricingDiff {
+            msrp_prices: diff_items(&self.msrp_prices, msrp_prices),
+            list_prices: diff_items(&self.list_prices, list_prices),
+            sale_prices: diff_sale_items(&self.sale_prices, sale_prices),
+            variant_prices: diff_variants(&self.variant_prices, variant_prices),
+        }
+    }
+
+    // Diff of the published prices and the draft updated with the prices
+    pub fn get_update_diff(
+        &self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+    ) -> PricingDiff {
+        let mut updated = self.clone();
+        let draft = updated.get_draft(String::new());
+        draft.update_prices(msrp_prices, list_prices, sale_prices);
+        self.get_diff(
+            &draft.msrp_prices,
+            &draft.list_prices,
+            &draft.sale_prices,
+            &draft.variant_prices,
+        )
+    }
+
+    fn get_draft_diff(&self) -> Option<PricingDiff> {
+        self.draft.as_ref().map(|draft| {
+            self.get_diff(
+                &draft.msrp_prices,
+                &draft.list_prices,
+                &draft.sale_prices,
+                &draft.variant_prices,
+            )
+        })
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct VariantPricing {
+    pub variant_id: String,
+    pub msrp_prices: Vec<PricingItem>,
+    pub list_prices: Vec<PricingItem>,
+    pub sale_prices: Vec<SalePricingItem>,
+}
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum PricingDraftStatus {
+    Draft,
+    Approved,
+}
+
+#[derive(Schema, Clone)]
+pub struct PricingDraft {
+    pub msrp_prices: Vec<PricingItem>,
+    pub list_prices: Vec<PricingItem>,
+    pub sale_prices: Vec<SalePricingItem>,
+    pub variant_prices: Vec<VariantPricing>,
+    pub status: PricingDraftStatus,
+    // Version of the published prices the draft is based on
+    pub base_version: u64,
+    pub updated_by: String,
+    // All users who edited the draft, none of them can approve it
+    pub editors: Vec<String>,
+    pub approved_by: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl PricingDraft {
+    fn new(
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        variant_prices: Vec<VariantPricing>,
+        base_version: u64,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            msrp_prices,
+            list_prices,
+            sale_prices,
+            variant_prices,
+            status: PricingDraftStatus::Draft,
+            base_version,
+            updated_by: String::new(),
+            editors: vec![],
+            approved_by: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    // Any edit invalidates a previous approval
+    fn reset_approval(&mut self) {
+        self.status = PricingDraftStatus::Draft;
+        self.approved_by = None;
         self.updated_at = chrono::Utc::now();
     }
 
+    fn set_prices(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+    ) {
+        self.msrp_prices = msrp_prices;
+        self.list_prices = list_prices;
+        self.sale_prices = sale_prices;
+        self.reset_approval();
+    }
+
     fn update_prices(
         &mut self,
         msrp_prices: Vec<PricingItem>,
@@ -49,10 +311,182 @@ impl Pricing {
         self.msrp_prices = merge_items(msrp_prices, self.msrp_prices.clone());
         self.list_prices = merge_items(list_prices, self.list_prices.clone());
         self.sale_prices = merge_sale_items(sale_prices, self.sale_prices.clone());
+        self.reset_approval();
+    }
+
+    fn update_variant_prices(
+        &mut self,
+        variant_id: String,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+    ) {
+        match self
+            .variant_prices
+            .iter_mut()
+            .find(|v| v.variant_id == variant_id)
+        {
+            Some(variant) => {
+                variant.msrp_prices = merge_items(msrp_prices, variant.msrp_prices.clone());
+                variant.list_prices = merge_items(list_prices, variant.list_prices.clone());
+                variant.sale_prices = merge_sale_items(sale_prices, variant.sale_prices.clone());
+            }
+            None => self.variant_prices.push(VariantPricing {
+                variant_id,
+                msrp_prices,
+                list_prices,
+                sale_prices,
+            }),
+        }
+        self.reset_approval();
+    }
+
+    fn remove_variant_prices(&mut self, variant_id: String) {
+        self.variant_prices.retain(|v| v.variant_id != variant_id);
+        self.reset_approval();
+    }
+
+    fn approve(&mut self, approver: String) {
+        self.status = PricingDraftStatus::Approved;
+        self.approved_by = Some(approver);
         self.updated_at = chrono::Utc::now();
     }
 }
 
+#[derive(Schema, Clone)]
+pub struct PricingItemChange {
+    pub currency: String,
+    pub zone: String,
+    pub old_price: Option<f32>,
+    pub new_price: Option<f32>,
+}
+
+#[derive(Schema, Clone)]
+pub struct SalePricingItemChange {
+    pub currency: String,
+    pub zone: String,
+    pub start: Option<chrono::DateTime<chrono::Utc>>,
+    pub end: Option<chrono::DateTime<chrono::Utc>>,
+    pub old_price: Option<f32>,
+    pub new_price: Option<f32>,
+}
+
+// Price the product is sold for, `regular_price` is set if the product is on sale
+#[derive(Schema, Clone)]
+pub struct EffectivePricingItem {
+    pub price: f32,
+    pub currency: String,
+    pub zone: String,
+    pub regular_price: Option<f32>,
+    pub on_sale: bool,
+}
+
+// Bundle price with the price of the components bought separately, if all components have a price
+#[derive(Schema, Clone)]
+pub struct BundlePricingItem {
+    pub price: f32,
+    pub currency: String,
+    pub zone: String,
+    pub components_price: Option<f32>,
+    pub savings: Option<f32>,
+}
+
+#[derive(Schema, Clone)]
+pub struct VariantPricingDiff {
+    pub variant_id: String,
+    pub msrp_prices: Vec<PricingItemChange>,
+    pub list_prices: Vec<PricingItemChange>,
+    pub sale_prices: Vec<SalePricingItemChange>,
+}
+
+#[derive(Schema, Clone)]
+pub struct PricingDiff {
+    pub msrp_prices: Vec<PricingItemChange>,
+    pub list_prices: Vec<PricingItemChange>,
+    pub sale_prices: Vec<SalePricingItemChange>,
+    pub variant_prices: Vec<VariantPricingDiff>,
+}
+
+impl PricingDiff {
+    pub fn is_empty(&self) -> bool {
+        self.msrp_prices.is_empty()
+            && self.list_prices.is_empty()
+            && self.sale_prices.is_empty()
+            && self.variant_prices.is_empty()
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct DraftNotFoundError {
+    pub message: String,
+}
+
+impl DraftNotFoundError {
+    fn new() -> DraftNotFoundError {
+        DraftNotFoundError {
+            message: "Draft pricing not found".to_string(),
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct PricingAlreadyExistsError {
+    pub message: String,
+    pub version: u64,
+}
+
+#[derive(Schema, Clone)]
+pub enum InitializePricingError {
+    PricingAlreadyExists(PricingAlreadyExistsError),
+}
+
+#[derive(Schema, Clone)]
+pub struct ApprovalNotAllowedError {
+    pub message: String,
+    pub editors: Vec<String>,
+}
+
+#[derive(Schema, Clone)]
+pub struct DraftNotApprovedError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct DraftOutdatedError {
+    pub message: String,
+    pub base_version: u64,
+    pub version: u64,
+}
+
+#[derive(Schema, Clone)]
+pub enum ApproveDraftPricingError {
+    DraftNotFound(DraftNotFoundError),
+    ApprovalNotAllowed(ApprovalNotAllowedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum PublishDraftPricingError {
+    DraftNotFound(DraftNotFoundError),
+    DraftNotApproved(DraftNotApprovedError),
+    DraftOutdated(DraftOutdatedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum DiscardDraftPricingError {
+    DraftNotFound(DraftNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub struct VariantPricingNotFoundError {
+    pub message: String,
+    pub variant_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum RemoveVariantPricingError {
+    VariantPricingNotFound(VariantPricingNotFoundError),
+}
+
 #[derive(Schema, Clone)]
 pub struct PricingItem {
     pub price: f32,
@@ -75,15 +509,15 @@ pub struct SalePricingItem {
     pub end: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+type SalePricingItemKey = (
+    String,
+    String,
+    Option<chrono::DateTime<chrono::Utc>>,
+    Option<chrono::DateTime<chrono::Utc>>,
+);
+
 impl SalePricingItem {
-    fn key(
-        &self,
-    ) -> (
-        String,
-        String,
-        Option<chrono::DateTime<chrono::Utc>>,
-        Option<chrono::DateTime<chrono::Utc>>,
-    ) {
+    fn key(&self) -> SalePricingItemKey {
         (
             self.zone.clone(),
             self.currency.clone(),
@@ -103,32 +537,52 @@ impl From<SalePricingItem> for PricingItem {
     }
 }
 
-fn get_price(currency: String, zone: String, pricing: Pricing) -> Option<PricingItem> {
+fn get_sale_price<'a>(
+    currency: &str,
+    zone: &str,
+    sale_prices: &'a [SalePricingItem],
+) -> Option<&'a SalePricingItem> {
     let now = chrono::Utc::now();
 
-    let sale_price = pricing.sale_prices.into_iter().find(|x| {
+    sale_prices.iter().find(|x| {
         x.zone == zone
             && x.currency == currency
             && x.start.is_none_or(|v| now >= v)
             && x.end.is_none_or(|v| now < v)
-    });
+    })
+}
+
+// List price, falling back to the MSRP
+fn get_regular_price(
+    currency: &str,
+    zone: &str,
+    msrp_prices: &[PricingItem],
+    list_prices: &[PricingItem],
+) -> Option<PricingItem> {
+    let list_price = list_prices
+        .iter()
+        .find(|x| x.zone == zone && x.currency == currency);
 
-    if sale_price.is_some() {
-        sale_price.map(|p| p.into())
+    if list_price.is_some() {
+        list_price.cloned()
     } else {
-        let list_price = pricing
-            .list_prices
-            .into_iter()
-            .find(|x| x.zone == zone && x.currency == currency);
+        msrp_prices
+            .iter()
+            .find(|x| x.zone == zone && x.currency == currency)
+            .cloned()
+    }
+}
 
-        if list_price.is_some() {
-            list_price
-        } else {
-            pricing
-                .msrp_prices
-                .into_iter()
-                .find(|x| x.zone == zone && x.currency == currency)
-        }
+fn get_price(
+    currency: &str,
+    zone: &str,
+    msrp_prices: &[PricingItem],
+    list_prices: &[PricingItem],
+    sale_prices: &[SalePricingItem],
+) -> Option<PricingItem> {
+    match get_sale_price(currency, zone, sale_prices) {
+        Some(sale_price) => Some(sale_price.clone().into()),
+        None => get_regular_price(currency, zone, msrp_prices, list_prices),
     }
 }
 
@@ -162,15 +616,7 @@ fn merge_sale_items(
     } else if current.is_empty() {
         updates
     } else {
-        let mut merge_map: HashMap<
-            (
-                String,
-                String,
-                Option<chrono::DateTime<chrono::Utc>>,
-                Option<chrono::DateTime<chrono::Utc>>,
-            ),
-            SalePricingItem,
-        > = HashMap::new();
+        let mut merge_map: HashMap<SalePricingItemKey, SalePricingItem> = HashMap::new();
 
         for item in updates {
             merge_map.insert(item.key(), item);
@@ -192,6 +638,117 @@ fn merge_sale_items(
     }
 }
 
+fn diff_items(current: &[PricingItem], updated: &[PricingItem]) -> Vec<PricingItemChange> {
+    let mut changes: BTreeMap<(String, String), PricingItemChange> = BTreeMap::new();
+
+    for item in current {
+        changes.insert(
+            item.key(),
+            PricingItemChange {
+                currency: item.currency.clone(),
+                zone: item.zone.clone(),
+                old_price: Some(item.price),
+                new_price: None,
+            },
+        );
+    }
+
+    for item in updated {
+        changes
+            .entry(item.key())
+            .or_insert(PricingItemChange {
+                currency: item.currency.clone(),
+                zone: item.zone.clone(),
+                old_price: None,
+                new_price: None,
+            })
+            .new_price = Some(item.price);
+    }
+
+    changes
+        .into_values()
+        .filter(|change| change.old_price != change.new_price)
+        .collect()
+}
+
+fn diff_sale_items(
+    current: &[SalePricingItem],
+    updated: &[SalePricingItem],
+) -> Vec<SalePricingItemChange> {
+    let mut changes: BTreeMap<SalePricingItemKey, SalePricingItemChange> = BTreeMap::new();
+
+    for item in current {
+        changes.insert(
+            item.key(),
+            SalePricingItemChange {
+                currency: item.currency.clone(),
+                zone: item.zone.clone(),
+                start: item.start,
+                end: item.end,
+                old_price: Some(item.price),
+                new_price: None,
+            },
+        );
+    }
+
+    for item in updated {
+        changes
+            .entry(item.key())
+            .or_insert(SalePricingItemChange {
+                currency: item.currency.clone(),
+                zone: item.zone.clone(),
+                start: item.start,
+                end: item.end,
+                old_price: None,
+                new_price: None,
+            })
+            .new_price = Some(item.price);
+    }
+
+    changes
+        .into_values()
+        .filter(|change| change.old_price != change.new_price)
+        .collect()
+}
+
+// Changes of the variant prices, variants without prices on one side are compared to no prices
+fn diff_variants(
+    current: &[VariantPricing],
+    updated: &[VariantPricing],
+) -> Vec<VariantPricingDiff> {
+    let variant_ids: BTreeSet<&String> = current
+        .iter()
+        .chain(updated.iter())
+        .map(|v| &v.variant_id)
+        .collect();
+
+    variant_ids
+        .into_iter()
+        .map(|variant_id| {
+            let current = current.iter().find(|v| &v.variant_id == variant_id);
+            let updated = updated.iter().find(|v| &v.variant_id == variant_id);
+            let msrp_prices =
+                |v: Option<&VariantPricing>| v.map_or(vec![], |v| v.msrp_prices.clone());
+            let list_prices =
+                |v: Option<&VariantPricing>| v.map_or(vec![], |v| v.list_prices.clone());
+            let sale_prices =
+                |v: Option<&VariantPricing>| v.map_or(vec![], |v| v.sale_prices.clone());
+
+            VariantPricingDiff {
+                variant_id: variant_id.clone(),
+                msrp_prices: diff_items(&msrp_prices(current), &msrp_prices(updated)),
+                list_prices: diff_items(&list_prices(current), &list_prices(updated)),
+                sale_prices: diff_sale_items(&sale_prices(current), &sale_prices(updated)),
+            }
+        })
+        .filter(|diff| {
+            !diff.msrp_prices.is_empty()
+                || !diff.list_prices.is_empty()
+                || !diff.sale_prices.is_empty()
+        })
+        .collect()
+}
+
 #[agent_definition]
 trait PricingAgent {
     fn new(id: String) -> Self;
@@ -200,19 +757,64 @@ trait PricingAgent {
 
     fn get_price(&self, currency: String, zone: String) -> Option<PricingItem>;
 
+    fn get_effective_price(&self, currency: String, zone: String) -> Option<EffectivePricingItem>;
+
+    fn get_variant_price(
+        &self,
+        variant_id: String,
+        currency: String,
+        zone: String,
+    ) -> Option<PricingItem>;
+
+    async fn get_bundle_price(&self, currency: String, zone: String) -> Option<BundlePricingItem>;
+
     fn initialize_pricing(
         &mut self,
         msrp_prices: Vec<PricingItem>,
         list_prices: Vec<PricingItem>,
         sale_prices: Vec<SalePricingItem>,
+    ) -> Result<(), InitializePricingError>;
+
+    fn get_draft_pricing(&self) -> Option<PricingDraft>;
+
+    fn get_draft_pricing_diff(&self) -> Option<PricingDiff>;
+
+    fn initialize_draft_pricing(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
+    );
+
+    fn update_draft_pricing(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
     );
 
-    fn update_pricing(
+    fn update_draft_variant_pricing(
         &mut self,
+        variant_id: String,
         msrp_prices: Vec<PricingItem>,
         list_prices: Vec<PricingItem>,
         sale_prices: Vec<SalePricingItem>,
+        author: String,
     );
+
+    fn remove_draft_variant_pricing(
+        &mut self,
+        variant_id: String,
+        author: String,
+    ) -> Result<(), RemoveVariantPricingError>;
+
+    fn approve_draft_pricing(&mut self, approver: String) -> Result<(), ApproveDraftPricingError>;
+
+    fn publish_draft_pricing(&mut self) -> Result<(), PublishDraftPricingError>;
+
+    fn discard_draft_pricing(&mut self) -> Result<(), DiscardDraftPricingError>;
 }
 
 struct PricingAgentImpl {
@@ -242,27 +844,247 @@ impl PricingAgent for PricingAgentImpl {
             .and_then(|pricing| pricing.get_price(currency, zone))
     }
 
+    fn get_effective_price(&self, currency: String, zone: String) -> Option<EffectivePricingItem> {
+        println!(
+            "Getting effective pricing for currency: {} zone: {}",
+            currency, zone
+        );
+        self.state
+            .as_ref()
+            .and_then(|pricing| pricing.get_effective_price(currency, zone))
+    }
+
+    fn get_variant_price(
+        &self,
+        variant_id: String,
+        currency: String,
+        zone: String,
+    ) -> Option<PricingItem> {
+        println!(
+            "Getting pricing for variant: {} currency: {} zone: {}",
+            variant_id, currency, zone
+        );
+        self.state
+            .as_ref()
+            .and_then(|pricing| pricing.get_variant_price(variant_id, currency, zone))
+    }
+
+    // Bundle price with the price of the components bought separately, components are read from the product
+    async fn get_bundle_price(&self, currency: String, zone: String) -> Option<BundlePricingItem> {
+        println!(
+            "Getting bundle pricing for currency: {} zone: {}",
+            currency, zone
+        );
+
+        let price = self
+            .state
+            .as_ref()
+            .and_then(|pricing| pricing.get_price(currency.clone(), zone.clone()))?;
+
+        let components = ProductAgentClient::get(self._id.clone())
+            .get_product()
+            .await
+            .filter(|product| product.is_bundle())?
+            .bundle_components;
+
+        let prices = get_prices(
+            components.iter().map(|c| c.product_id.clone()).collect(),
+            currency.clone(),
+            zone.clone(),
+        )
+        .await;
+
+        let components_price: Option<f32> = components
+            .iter()
+            .map(|c| {
+                prices
+                    .get(&c.product_id)
+                    .and_then(|p| p.price())
+                    .map(|p| p.price * c.quantity as f32)
+            })
+            .sum();
+
+        Some(BundlePricingItem {
+            price: price.price,
+            currency,
+            zone,
+            components_price,
+            savings: components_price.map(|p| p - price.price),
+        })
+    }
+
     fn get_pricing(&self) -> Option<Pricing> {
         self.state.clone()
     }
 
+    // Initial prices are published directly, later changes go through the draft workflow
     fn initialize_pricing(
         &mut self,
         msrp_prices: Vec<PricingItem>,
         list_prices: Vec<PricingItem>,
         sale_prices: Vec<SalePricingItem>,
+    ) -> Result<(), InitializePricingError> {
+        match self.state.as_ref() {
+            Some(pricing) if pricing.version > 0 || pricing.draft.is_some() => Err(
+                InitializePricingError::PricingAlreadyExists(PricingAlreadyExistsError {
+                    message: "Pricing already exists, use draft pricing to change it".to_string(),
+                    version: pricing.version,
+                }),
+            ),
+            _ => {
+                self.get_state()
+                    .set_prices(msrp_prices, list_prices, sale_prices);
+                Ok(())
+            }
+        }
+    }
+
+    fn get_draft_pricing(&self) -> Option<PricingDraft> {
+        self.state
+            .as_ref()
+            .and_then(|pricing| pricing.draft.clone())
+    }
+
+    fn get_draft_pricing_diff(&self) -> Option<PricingDiff> {
+        self.state
+            .as_ref()
+            .and_then(|pricing| pricing.get_draft_diff())
+    }
+
+    fn initialize_draft_pricing(
+        &mut self,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
     ) {
+        println!("Initializing draft pricing by {}", author);
         self.get_state()
-            .set_prices(msrp_prices, list_prices, sale_prices);
+            .set_draft_prices(msrp_prices, list_prices, sale_prices, author);
     }
 
-    fn update_pricing(
+    fn update_draft_pricing(
         &mut self,
         msrp_prices: Vec<PricingItem>,
         list_prices: Vec<PricingItem>,
         sale_prices: Vec<SalePricingItem>,
+        author: String,
     ) {
+        println!("Updating draft pricing by {}", author);
         self.get_state()
-            .update_prices(msrp_prices, list_prices, sale_prices);
+            .update_draft_prices(msrp_prices, list_prices, sale_prices, author);
+    }
+
+    fn update_draft_variant_pricing(
+        &mut self,
+        variant_id: String,
+        msrp_prices: Vec<PricingItem>,
+        list_prices: Vec<PricingItem>,
+        sale_prices: Vec<SalePricingItem>,
+        author: String,
+    ) {
+        println!(
+            "Updating draft pricing of variant {} by {}",
+            variant_id, author
+        );
+        self.get_state().update_draft_variant_prices(
+            variant_id,
+            msrp_prices,
+            list_prices,
+            sale_prices,
+            author,
+        );
+    }
+
+    fn remove_draft_variant_pricing(
+        &mut self,
+        variant_id: String,
+        author: String,
+    ) -> Result<(), RemoveVariantPricingError> {
+        println!(
+            "Removing draft pricing of variant {} by {}",
+            variant_id, author
+        );
+        let removed = self
+            .state
+            .as_mut()
+            .is_some_and(|pricing| pricing.remove_draft_variant_prices(variant_id.clone(), author));
+
+        if removed {
+            Ok(())
+        } else {
+            Err(RemoveVariantPricingError::VariantPricingNotFound(
+                VariantPricingNotFoundError {
+                    message: "Variant pricing not found".to_string(),
+                    variant_id,
+                },
+            ))
+        }
+    }
+
+    fn approve_draft_pricing(&mut self, approver: String) -> Result<(), ApproveDraftPricingError> {
+        println!("Approving draft pricing by {}", approver);
+        match self
+            .state
+            .as_mut()
+            .and_then(|pricing| pricing.draft.as_mut())
+        {
+            Some(draft) if draft.editors.contains(&approver) => Err(
+                ApproveDraftPricingError::ApprovalNotAllowed(ApprovalNotAllowedError {
+                    message: "Draft pricing can not be approved by its editors".to_string(),
+                    editors: draft.editors.clone(),
+                }),
+            ),
+            Some(draft) => {
+                draft.approve(approver);
+                Ok(())
+            }
+            None => Err(ApproveDraftPricingError::DraftNotFound(
+                DraftNotFoundError::new(),
+            )),
+        }
+    }
+
+    fn publish_draft_pricing(&mut self) -> Result<(), PublishDraftPricingError> {
+        println!("Publishing draft pricing");
+        let version = self.state.as_ref().map_or(0, |pricing| pricing.version);
+        let draft = self
+            .state
+            .as_ref()
+            .and_then(|pricing| pricing.draft.clone());
+        match draft {
+            // Published prices changed since the draft was created, publishing would overwrite the changes
+            Some(draft) if draft.base_version != version => Err(
+                PublishDraftPricingError::DraftOutdated(DraftOutdatedError {
+                    message: "Published pricing changed since the draft was created".to_string(),
+                    base_version: draft.base_version,
+                    version,
+                }),
+            ),
+            Some(draft) if draft.status == PricingDraftStatus::Approved => {
+                self.get_state().publish_draft(draft);
+                Ok(())
+            }
+            Some(_) => Err(PublishDraftPricingError::DraftNotApproved(
+                DraftNotApprovedError {
+                    message: "Draft pricing not approved".to_string(),
+                },
+            )),
+            None => Err(PublishDraftPricingError::DraftNotFound(
+                DraftNotFoundError::new(),
+            )),
+        }
+    }
+
+    fn discard_draft_pricing(&mut self) -> Result<(), DiscardDraftPricingError> {
+        println!("Discarding draft pricing");
+        let draft = self.state.as_mut().and_then(|pricing| pricing.draft.take());
+        if draft.is_some() {
+            Ok(())
+        } else {
+            Err(DiscardDraftPricingError::DraftNotFound(
+                DraftNotFoundError::new(),
+            ))
+        }
     }
 }
@@ -0,0 +1,399 @@
+use crate::common::{ImportFormat, ImportRecord};
+use crate::import_job::{
+    get_import_rows, ImportJob, ImportRow, ImportRowStatus, ImportStatus, LoadImportError,
+    RunImportError,
+};
+use crate::pricing::{
+    ApproveDraftPricingError, Pricing, PricingAgentClient, PricingDiff, PricingItem,
+    PublishDraftPricingError, SalePricingItem,
+};
+use crate::product::ProductAgentClient;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PricingImportItem {
+    price: f32,
+    currency: String,
+    zone: String,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct SalePricingImportItem {
+    price: f32,
+    currency: String,
+    zone: String,
+    start: Option<String>,
+    end: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PricingImportBody {
+    product_id: Option<String>,
+    #[serde(default)]
+    msrp_prices: Vec<PricingImportItem>,
+    #[serde(default)]
+    list_prices: Vec<PricingImportItem>,
+    #[serde(default)]
+    sale_prices: Vec<SalePricingImportItem>,
+}
+
+#[derive(Schema, Clone)]
+pub struct PricingImportRow {
+    pub row_number: u32,
+    pub product_id: String,
+    pub msrp_prices: Vec<PricingItem>,
+    pub list_prices: Vec<PricingItem>,
+    pub sale_prices: Vec<SalePricingItem>,
+    pub status: ImportRowStatus,
+    pub errors: Vec<String>,
+    pub diff: Option<PricingDiff>,
+}
+
+impl PricingImportRow {
+    fn invalid(row_number: u32, product_id: String, errors: Vec<String>) -> Self {
+        Self {
+            row_number,
+            product_id,
+            msrp_prices: vec![],
+            list_prices: vec![],
+            sale_prices: vec![],
+            status: ImportRowStatus::Invalid,
+            errors,
+            diff: None,
+        }
+    }
+}
+
+impl ImportRow for PricingImportRow {
+    fn product_id(&self) -> &str {
+        &self.product_id
+    }
+
+    fn status(&self) -> ImportRowStatus {
+        self.status
+    }
+
+    fn set_status(&mut self, status: ImportRowStatus) {
+        self.status = status;
+    }
+
+    fn errors_mut(&mut self) -> &mut Vec<String> {
+        &mut self.errors
+    }
+}
+
+fn get_report(state: &ImportJob<PricingImportRow>) -> PricingImportReport {
+    PricingImportReport {
+        import_id: state.import_id.clone(),
+        status: state.status,
+        total: state.rows.len() as u32,
+        invalid: state.count(ImportRowStatus::Invalid),
+        pending: state.count(ImportRowStatus::Pending),
+        updated: state.count(ImportRowStatus::Updated),
+        approved: state.count(ImportRowStatus::Approved),
+        published: state.count(ImportRowStatus::Published),
+        failed: state.count(ImportRowStatus::Failed),
+        rows: state.rows.clone(),
+        updated_at: state.updated_at,
+    }
+}
+
+// Rows are applied to the pricing drafts of the products, updated rows are drafts to approve,
+// approved rows are drafts to publish
+#[derive(Schema, Clone)]
+pub struct PricingImportReport {
+    pub import_id: String,
+    pub status: ImportStatus,
+    pub total: u32,
+    pub invalid: u32,
+    pub pending: u32,
+    pub updated: u32,
+    pub approved: u32,
+    pub published: u32,
+    pub failed: u32,
+    pub rows: Vec<PricingImportRow>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn parse_date(
+    value: Option<String>,
+    field: &str,
+    errors: &mut Vec<String>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    value.and_then(|v| match chrono::DateTime::parse_from_rfc3339(&v) {
+        Ok(date) => Some(date.with_timezone(&chrono::Utc)),
+        Err(e) => {
+            errors.push(format!("Invalid {field} date '{v}': {e}"));
+            None
+        }
+    })
+}
+
+fn validate_price(price: f32, currency: &str, zone: &str, errors: &mut Vec<String>) {
+    if !price.is_finite() || price < 0.0 {
+        errors.push(format!("Invalid price {price} for {currency}/{zone}"));
+    }
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        errors.push(format!("Invalid currency '{currency}'"));
+    }
+    if zone.trim().is_empty() {
+        errors.push("Empty pricing zone".to_string());
+    }
+}
+
+fn get_pricing_items(items: Vec<PricingImportItem>, errors: &mut Vec<String>) -> Vec<PricingItem> {
+    items
+        .into_iter()
+        .map(|item| {
+            validate_price(item.price, &item.currency, &item.zone, errors);
+            PricingItem {
+                price: item.price,
+                currency: item.currency,
+                zone: item.zone,
+            }
+        })
+        .collect()
+}
+
+fn get_sale_pricing_items(
+    items: Vec<SalePricingImportItem>,
+    errors: &mut Vec<String>,
+) -> Vec<SalePricingItem> {
+    items
+        .into_iter()
+        .map(|item| {
+            validate_price(item.price, &item.currency, &item.zone, errors);
+            let start = parse_date(item.start, "start", errors);
+            let end = parse_date(item.end, "end", errors);
+            if let (Some(start), Some(end)) = (start, end) {
+                if start >= end {
+                    errors.push(format!(
+                        "Sale price for {}/{} ends before it starts",
+                        item.currency, item.zone
+                    ));
+                }
+            }
+            SalePricingItem {
+                price: item.price,
+                currency: item.currency,
+                zone: item.zone,
+                start,
+                end,
+            }
+        })
+        .collect()
+}
+
+fn get_import_row(record: ImportRecord) -> PricingImportRow {
+    if let Some(error) = record.error {
+        return PricingImportRow::invalid(record.row_number, String::new(), vec![error]);
+    }
+
+    let body = match serde_json::from_str::<PricingImportBody>(&record.body) {
+        Ok(body) => body,
+        Err(e) => {
+            return PricingImportRow::invalid(
+                record.row_number,
+                record.id.unwrap_or_default(),
+                vec![format!("Invalid body: {e}")],
+            )
+        }
+    };
+
+    let mut errors = Vec::new();
+
+    let product_id = match (record.id, body.product_id) {
+        (Some(id), Some(body_id)) if id != body_id => {
+            errors.push(format!(
+                "Product id '{id}' does not match body product id '{body_id}'"
+            ));
+            id
+        }
+        (Some(id), _) => id,
+        (None, Some(body_id)) => body_id,
+        (None, None) => String::new(),
+    };
+
+    if product_id.trim().is_empty() {
+        errors.push("Empty product id".to_string());
+    }
+
+    if body.msrp_prices.is_empty() && body.list_prices.is_empty() && body.sale_prices.is_empty() {
+        errors.push("No prices".to_string());
+    }
+
+    let msrp_prices = get_pricing_items(body.msrp_prices, &mut errors);
+    let list_prices = get_pricing_items(body.list_prices, &mut errors);
+    let sale_prices = get_sale_pricing_items(body.sale_prices, &mut errors);
+
+    if errors.is_empty() {
+        PricingImportRow {
+            row_number: record.row_number,
+            product_id,
+            msrp_prices,
+            list_prices,
+            sale_prices,
+            status: ImportRowStatus::Pending,
+            errors,
+            diff: None,
+        }
+    } else {
+        PricingImportRow::invalid(record.row_number, product_id, errors)
+    }
+}
+
+async fn get_row_diff(row: PricingImportRow) -> PricingDiff {
+    let pricing = PricingAgentClient::get(row.product_id.clone())
+        .get_pricing()
+        .await
+        .unwrap_or(Pricing::new(row.product_id));
+
+    pricing.get_update_diff(row.msrp_prices, row.list_prices, row.sale_prices)
+}
+
+// Update the draft pricing of the product, the draft is published once approved
+async fn apply_row(row: PricingImportRow, author: String) -> Result<ImportRowStatus, String> {
+    let product_client = ProductAgentClient::get(row.product_id.clone());
+    let mut pricing_client = PricingAgentClient::get(row.product_id.clone());
+
+    if product_client.get_product().await.is_none() {
+        return Err("Product not found".to_string());
+    }
+
+    pricing_client
+        .update_draft_pricing(row.msrp_prices, row.list_prices, row.sale_prices, author)
+        .await;
+
+    Ok(ImportRowStatus::Updated)
+}
+
+async fn approve_row(row: PricingImportRow, approver: String) -> Result<ImportRowStatus, String> {
+    PricingAgentClient::get(row.product_id)
+        .approve_draft_pricing(approver)
+        .await
+        .map(|_| ImportRowStatus::Approved)
+        .map_err(|error| match error {
+            ApproveDraftPricingError::DraftNotFound(e) => e.message,
+            ApproveDraftPricingError::ApprovalNotAllowed(e) => e.message,
+        })
+}
+
+async fn publish_row(row: PricingImportRow) -> Result<ImportRowStatus, String> {
+    PricingAgentClient::get(row.product_id)
+        .publish_draft_pricing()
+        .await
+        .map(|_| ImportRowStatus::Published)
+        .map_err(|error| match error {
+            PublishDraftPricingError::DraftNotFound(e) => e.message,
+            PublishDraftPricingError::DraftNotApproved(e) => e.message,
+            PublishDraftPricingError::DraftOutdated(e) => e.message,
+        })
+}
+
+#[agent_definition]
+trait PricingImportAgent {
+    fn new(id: String) -> Self;
+
+    fn get_import(&self) -> Option<PricingImportReport>;
+
+    fn load(
+        &mut self,
+        data: String,
+        format: ImportFormat,
+        has_header: bool,
+    ) -> Result<PricingImportReport, LoadImportError>;
+
+    async fn dry_run(&mut self) -> Result<PricingImportReport, RunImportError>;
+
+    async fn apply(&mut self, author: String) -> Result<PricingImportReport, RunImportError>;
+
+    async fn approve(&mut self, approver: String) -> Result<PricingImportReport, RunImportError>;
+
+    async fn publish(&mut self) -> Result<PricingImportReport, RunImportError>;
+}
+
+struct PricingImportAgentImpl {
+    _id: String,
+    state: Option<ImportJob<PricingImportRow>>,
+}
+
+impl PricingImportAgentImpl {
+    fn get_state(&mut self) -> &mut ImportJob<PricingImportRow> {
+        self.state.get_or_insert(ImportJob::new(self._id.clone()))
+    }
+}
+
+#[agent_implementation]
+impl PricingImportAgent for PricingImportAgentImpl {
+    fn new(id: String) -> Self {
+        PricingImportAgentImpl {
+            _id: id,
+            state: None,
+        }
+    }
+
+    fn get_import(&self) -> Option<PricingImportReport> {
+        self.state.as_ref().map(get_report)
+    }
+
+    fn load(
+        &mut self,
+        data: String,
+        format: ImportFormat,
+        has_header: bool,
+    ) -> Result<PricingImportReport, LoadImportError> {
+        let state = self.get_state();
+        println!("Loading pricing import {}", state.import_id);
+
+        state.load(get_import_rows(&data, format, has_header, get_import_row))?;
+        Ok(get_report(state))
+    }
+
+    async fn dry_run(&mut self) -> Result<PricingImportReport, RunImportError> {
+        let state = self.get_state();
+        println!("Dry run of pricing import {}", state.import_id);
+
+        state
+            .inspect(get_row_diff, |row, diff| row.diff = Some(diff))
+            .await?;
+        Ok(get_report(state))
+    }
+
+    async fn apply(&mut self, author: String) -> Result<PricingImportReport, RunImportError> {
+        let state = self.get_state();
+        println!("Applying pricing import {} by {}", state.import_id, author);
+
+        state.apply(|row| apply_row(row, author.clone())).await?;
+        Ok(get_report(state))
+    }
+
+    async fn approve(&mut self, approver: String) -> Result<PricingImportReport, RunImportError> {
+        let state = self.get_state();
+        println!(
+            "Approving pricing import {} by {}",
+            state.import_id, approver
+        );
+
+        state
+            .update_applied_rows(
+                |row| row.status == ImportRowStatus::Updated,
+                |row| approve_row(row, approver.clone()),
+            )
+            .await?;
+        Ok(get_report(state))
+    }
+
+    async fn publish(&mut self) -> Result<PricingImportReport, RunImportError> {
+        let state = self.get_state();
+        println!("Publishing pricing import {}", state.import_id);
+
+        state
+            .update_applied_rows(|row| row.status == ImportRowStatus::Approved, publish_row)
+            .await?;
+        Ok(get_report(state))
+    }
+}
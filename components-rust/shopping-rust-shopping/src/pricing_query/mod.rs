@@ -0,0 +1,156 @@
+use crate::common::{parse_ids, CURRENCY_DEFAULT, PRICING_ZONE_DEFAULT};
+use crate::pricing::{EffectivePricingItem, PricingAgentClient, PricingItem};
+use futures::stream::{self, StreamExt};
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::{HashMap, HashSet};
+
+pub const PRICING_QUERY_CONCURRENCY: usize = 10;
+
+#[derive(Schema, Clone)]
+pub struct PricingNotFoundError {
+    pub message: String,
+    pub product_id: String,
+}
+
+impl PricingNotFoundError {
+    fn new(product_id: String) -> PricingNotFoundError {
+        PricingNotFoundError {
+            message: "Pricing not found".to_string(),
+            product_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub enum ProductPriceResult {
+    Found(PricingItem),
+    NotFound(PricingNotFoundError),
+}
+
+impl ProductPriceResult {
+    pub fn price(&self) -> Option<PricingItem> {
+        match self {
+            ProductPriceResult::Found(price) => Some(price.clone()),
+            ProductPriceResult::NotFound(_) => None,
+        }
+    }
+}
+
+pub async fn get_prices(
+    product_ids: Vec<String>,
+    currency: String,
+    zone: String,
+) -> HashMap<String, ProductPriceResult> {
+    let unique_ids: HashSet<String> = product_ids.into_iter().collect();
+
+    stream::iter(unique_ids)
+        .map(|product_id| {
+            let currency = currency.clone();
+            let zone = zone.clone();
+            async move {
+                let price = PricingAgentClient::get(product_id.clone())
+                    .get_price(currency, zone)
+                    .await;
+
+                let result = match price {
+                    Some(price) => ProductPriceResult::Found(price),
+                    None => {
+                        ProductPriceResult::NotFound(PricingNotFoundError::new(product_id.clone()))
+                    }
+                };
+
+                (product_id, result)
+            }
+        })
+        .buffer_unordered(PRICING_QUERY_CONCURRENCY)
+        .collect()
+        .await
+}
+
+// Effective prices of the products, products without price are not included
+pub async fn get_effective_prices(
+    product_ids: Vec<String>,
+    currency: String,
+    zone: String,
+) -> HashMap<String, EffectivePricingItem> {
+    let unique_ids: HashSet<String> = product_ids.into_iter().collect();
+
+    stream::iter(unique_ids)
+        .map(|product_id| {
+            let currency = currency.clone();
+            let zone = zone.clone();
+            async move {
+                let price = PricingAgentClient::get(product_id.clone())
+                    .get_effective_price(currency, zone)
+                    .await;
+
+                (product_id, price)
+            }
+        })
+        .buffer_unordered(PRICING_QUERY_CONCURRENCY)
+        .filter_map(|(product_id, price)| async move { price.map(|price| (product_id, price)) })
+        .collect()
+        .await
+}
+
+#[agent_definition(mode = "ephemeral")]
+trait PricingQueryAgent {
+    fn new() -> Self;
+
+    async fn get_prices(
+        &self,
+        product_ids: Vec<String>,
+        currency: String,
+        zone: String,
+    ) -> HashMap<String, ProductPriceResult>;
+
+    async fn get_prices_by_ids(
+        &self,
+        ids: String,
+        currency: Option<String>,
+        zone: Option<String>,
+    ) -> HashMap<String, ProductPriceResult>;
+}
+
+struct PricingQueryAgentImpl {}
+
+#[agent_implementation]
+impl PricingQueryAgent for PricingQueryAgentImpl {
+    fn new() -> Self {
+        PricingQueryAgentImpl {}
+    }
+
+    async fn get_prices(
+        &self,
+        product_ids: Vec<String>,
+        currency: String,
+        zone: String,
+    ) -> HashMap<String, ProductPriceResult> {
+        println!(
+            "Getting prices for {} products - currency: {} zone: {}",
+            product_ids.len(),
+            currency,
+            zone
+        );
+        get_prices(product_ids, currency, zone).await
+    }
+
+    // Currency and pricing zone default to USD and global, if not set
+    async fn get_prices_by_ids(
+        &self,
+        ids: String,
+        currency: Option<String>,
+        zone: Option<String>,
+    ) -> HashMap<String, ProductPriceResult> {
+        let product_ids = parse_ids(&ids);
+        let currency = currency.unwrap_or(CURRENCY_DEFAULT.to_string());
+        let zone = zone.unwrap_or(PRICING_ZONE_DEFAULT.to_string());
+        println!(
+            "Getting prices for {} products - currency: {} zone: {}",
+            product_ids.len(),
+            currency,
+            zone
+        );
+        get_prices(product_ids, currency, zone).await
+    }
+}
@@ -1,4 +1,19 @@
+use crate::attribute::{
+    validate_attribute_values, validate_attributes, AttributeDefinition,
+    AttributeSchemaAgentClient, ProductAttribute,
+};
+use crate::category::{CategoryAgentClient, CategoryNotFoundError};
+use crate::common::{get_locale_chain, normalize_locale, LOCALE_DEFAULT};
+use crate::media::{
+    decode_media, delete_media, encode_media, get_object_name, read_media, write_media,
+    MediaContent, MediaKind, ProductMedia, UploadMedia,
+};
+use crate::product_query::get_products;
+use crate::product_search::{get_component_id, get_other_products};
+use crate::product_suggest::trigger_index_product;
+use futures::future::join_all;
 use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::HashSet;
 
 #[derive(Schema, Clone)]
 pub struct Product {
@@ -7,23 +22,880 @@ pub struct Product {
     pub brand: String,
     pub description: String,
     pub tags: Vec<String>,
+    pub status: ProductStatus,
+    pub options: Vec<ProductOption>,
+    pub variants: Vec<ProductVariant>,
+    pub category_ids: Vec<String>,
+    pub attributes: Vec<ProductAttribute>,
+    pub media: Vec<ProductMedia>,
+    pub rating: ProductRating,
+    pub translations: Vec<ProductTranslation>,
+    pub links: Vec<ProductLink>,
+    pub bundle_components: Vec<BundleComponent>,
+    pub availability: ProductAvailability,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl Product {
+    pub fn is_available(&self) -> bool {
+        self.status == ProductStatus::Active
+    }
+
+    pub fn is_bundle(&self) -> bool {
+        !self.bundle_components.is_empty()
+    }
+
+    fn update(&mut self, data: UpdateProduct) {
+        if let Some(name) = data.name {
+            self.name = name;
+        }
+        if let Some(brand) = data.brand {
+            self.brand = brand;
+        }
+        if let Some(description) = data.description {
+            self.description = description;
+        }
+        if let Some(tags) = data.tags {
+            self.tags = tags;
+        }
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn set_status(&mut self, status: ProductStatus) {
+        self.status = status;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn set_category_ids(&mut self, category_ids: Vec<String>) {
+        self.category_ids = category_ids;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&ProductAttribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name.eq_ignore_ascii_case(name))
+    }
+
+    fn get_translation(&self, locale: &str) -> Option<&ProductTranslation> {
+        self.translations.iter().find(|t| t.locale == locale)
+    }
+
+    // Product with name, description and tags in the given locale, following the locale fallback chain,
+    // the product content itself is in the default locale
+    pub fn localized(&self, locale: &str) -> Product {
+        let translations: Vec<&ProductTranslation> = get_locale_chain(locale)
+            .iter()
+            .take_while(|l| l.as_str() != LOCALE_DEFAULT)
+            .filter_map(|l| self.get_translation(l))
+            .collect();
+
+        let mut product = self.clone();
+
+        if let Some(name) = translations.iter().find_map(|t| t.name.clone()) {
+            product.name = name;
+        }
+        if let Some(description) = translations.iter().find_map(|t| t.description.clone()) {
+            product.description = description;
+        }
+        if let Some(tags) = translations.iter().find_map(|t| t.tags.clone()) {
+            product.tags = tags;
+        }
+
+        product
+    }
+
+    fn set_translation(&mut self, translation: ProductTranslation) {
+        match self
+            .translations
+            .iter_mut()
+            .find(|t| t.locale == translation.locale)
+        {
+            Some(existing) => *existing = translation,
+            None => self.translations.push(translation),
+        }
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn remove_translation(&mut self, locale: &str) -> bool {
+        let exist = self.translations.iter().any(|t| t.locale == locale);
+
+        if exist {
+            self.translations.retain(|t| t.locale != locale);
+            self.updated_at = chrono::Utc::now();
+        }
+
+        exist
+    }
+
+    fn set_bundle_components(&mut self, components: Vec<BundleComponent>) {
+        self.bundle_components = components;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn set_availability(&mut self, availability: ProductAvailability) {
+        self.availability = availability;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    // Product is sellable in the pricing zone and, if known, the country
+    pub fn is_sellable_in(&self, zone: &str, country: Option<&str>) -> bool {
+        self.availability.is_zone_allowed(zone)
+            && country.is_none_or(|country| self.availability.is_country_allowed(country))
+    }
+
+    fn add_link(&mut self, link: ProductLink) -> bool {
+        let exist = self.links.contains(&link);
+
+        if !exist {
+            self.links.push(link);
+            self.updated_at = chrono::Utc::now();
+        }
+
+        !exist
+    }
+
+    fn remove_link(&mut self, link: &ProductLink) -> bool {
+        let exist = self.links.contains(link);
+
+        if exist {
+            self.links.retain(|l| l != link);
+            self.updated_at = chrono::Utc::now();
+        }
+
+        exist
+    }
+
+    fn set_rating(&mut self, rating: ProductRating) {
+        self.rating = rating;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn set_attributes(&mut self, attributes: Vec<ProductAttribute>) {
+        self.attributes = attributes;
+        self.updated_at = chrono::Utc::now();
+    }
+
+    pub fn get_variant(&self, variant_id: &str) -> Option<ProductVariant> {
+        self.variants
+            .iter()
+            .find(|variant| variant.variant_id == variant_id)
+            .cloned()
+    }
+
+    fn set_options(&mut self, options: Vec<ProductOption>) -> Result<(), String> {
+        for variant in self.variants.iter() {
+            validate_variant_options(&options, &variant.options)
+                .map_err(|e| format!("Variant {}: {}", variant.variant_id, e))?;
+        }
+        self.options = options;
+        self.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn add_variant(&mut self, variant: ProductVariant) -> Result<(), String> {
+        validate_variant_options(&self.options, &variant.options)?;
+
+        if self
+            .variants
+            .iter()
+            .any(|v| same_options(&v.options, &variant.options))
+        {
+            return Err("Variant with the same options already exists".to_string());
+        }
+
+        self.variants.push(variant);
+        self.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn remove_variant(&mut self, variant_id: &str) -> bool {
+        let exist = self.variants.iter().any(|v| v.variant_id == variant_id);
+
+        if exist {
+            self.variants.retain(|v| v.variant_id != variant_id);
+            self.updated_at = chrono::Utc::now();
+        }
+
+        exist
+    }
+
+    pub fn get_media(&self, media_id: &str) -> Option<ProductMedia> {
+        self.media.iter().find(|m| m.media_id == media_id).cloned()
+    }
+
+    // Add or replace media, keeping the position of replaced media
+    fn add_media(&mut self, media: ProductMedia) {
+        if media.primary {
+            self.media.iter_mut().for_each(|m| m.primary = false);
+        }
+
+        match self.media.iter_mut().find(|m| m.media_id == media.media_id) {
+            Some(existing) => *existing = media,
+            None => self.media.push(media),
+        }
+
+        self.ensure_primary_media();
+        self.updated_at = chrono::Utc::now();
+    }
+
+    fn remove_media(&mut self, media_id: &str) -> bool {
+        let exist = self.media.iter().any(|m| m.media_id == media_id);
+
+        if exist {
+            self.media.retain(|m| m.media_id != media_id);
+            self.ensure_primary_media();
+            self.updated_at = chrono::Utc::now();
+        }
+
+        exist
+    }
+
+    fn reorder_media(&mut self, media_ids: &[String]) -> Result<(), String> {
+        let mut media = Vec::new();
+
+        for media_id in media_ids {
+            let item = self
+                .get_media(media_id)
+                .ok_or(format!("Media {} not found", media_id))?;
+            if media
+                .iter()
+                .any(|m: &ProductMedia| m.media_id == item.media_id)
+            {
+                return Err(format!("Media {} listed more than once", media_id));
+            }
+            media.push(item);
+        }
+
+        if media.len() != self.media.len() {
+            return Err("All product media have to be listed".to_string());
+        }
+
+        self.media = media;
+        self.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn set_primary_media(&mut self, media_id: &str) -> Result<(), String> {
+        match self.get_media(media_id) {
+            Some(media) if media.kind == MediaKind::Image => {
+                self.media
+                    .iter_mut()
+                    .for_each(|m| m.primary = m.media_id == media_id);
+                self.updated_at = chrono::Utc::now();
+                Ok(())
+            }
+            Some(_) => Err("Only images can be primary media".to_string()),
+            None => Err(format!("Media {} not found", media_id)),
+        }
+    }
+
+    // First image is primary, if no primary image is set
+    fn ensure_primary_media(&mut self) {
+        if !self.media.iter().any(|m| m.primary) {
+            if let Some(image) = self.media.iter_mut().find(|m| m.kind == MediaKind::Image) {
+                image.primary = true;
+            }
+        }
+    }
+}
+
+// Translated product content, missing fields fall back to the next locale in the chain
+#[derive(Schema, Clone)]
+pub struct ProductTranslation {
+    pub locale: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Schema, Clone)]
+pub struct UpdateTranslation {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ProductLinkType {
+    Accessory,
+    Alternative,
+    BundleComponent,
+    Upsell,
+}
+
+// Typed link to another product
+#[derive(Schema, Clone, Eq, PartialEq)]
+pub struct ProductLink {
+    pub product_id: String,
+    pub link_type: ProductLinkType,
+}
+
+// Countries (ISO 3166-1 alpha-2 codes) and pricing zones where the product can be sold,
+// empty allowed lists mean no restriction, blocked entries take precedence
+#[derive(Schema, Clone, Default)]
+pub struct ProductAvailability {
+    pub allowed_countries: Vec<String>,
+    pub blocked_countries: Vec<String>,
+    pub allowed_zones: Vec<String>,
+    pub blocked_zones: Vec<String>,
+}
+
+impl ProductAvailability {
+    fn is_allowed(allowed: &[String], blocked: &[String], value: &str) -> bool {
+        !blocked.iter().any(|v| v.eq_ignore_ascii_case(value))
+            && (allowed.is_empty() || allowed.iter().any(|v| v.eq_ignore_ascii_case(value)))
+    }
+
+    pub fn is_country_allowed(&self, country: &str) -> bool {
+        Self::is_allowed(&self.allowed_countries, &self.blocked_countries, country)
+    }
+
+    pub fn is_zone_allowed(&self, zone: &str) -> bool {
+        Self::is_allowed(&self.allowed_zones, &self.blocked_zones, zone)
+    }
+
+    fn normalized(self) -> Self {
+        fn normalize(values: Vec<String>) -> Vec<String> {
+            let mut values: Vec<String> = values
+                .into_iter()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect();
+            values.sort();
+            values.dedup();
+            values
+        }
+
+        let upper = |values: Vec<String>| values.into_iter().map(|v| v.to_uppercase()).collect();
+
+        Self {
+            allowed_countries: normalize(upper(self.allowed_countries)),
+            blocked_countries: normalize(upper(self.blocked_countries)),
+            allowed_zones: normalize(self.allowed_zones),
+            blocked_zones: normalize(self.blocked_zones),
+        }
+    }
+}
+
+// Component of a bundle product, with the quantity contained in one bundle
+#[derive(Schema, Clone)]
+pub struct BundleComponent {
+    pub product_id: String,
+    pub quantity: u32,
+}
+
+// Bundle component of a cart or order line
+#[derive(Schema, Clone)]
+pub struct BundleComponentItem {
+    pub product_id: String,
+    pub product_name: String,
+    pub product_brand: String,
+    pub quantity: u32,
+}
+
+impl BundleComponentItem {
+    pub fn new(component: &BundleComponent, product: Product) -> Self {
+        Self {
+            product_id: product.product_id,
+            product_name: product.name,
+            product_brand: product.brand,
+            quantity: component.quantity,
+        }
+    }
+}
+
+pub enum BundleComponentError {
+    ProductNotFound(String),
+    ProductNotAvailable(String),
+    ProductNotSellable(String),
+}
+
+// Bundle component items, all components have to exist, be available and sellable in the zone and country
+pub async fn get_bundle_component_items(
+    product: &Product,
+    zone: &str,
+    country: Option<&str>,
+) -> Result<Vec<BundleComponentItem>, BundleComponentError> {
+    let product_ids: Vec<String> = product
+        .bundle_components
+        .iter()
+        .map(|c| c.product_id.clone())
+        .collect();
+
+    let products = get_products(&product_ids).await;
+
+    product
+        .bundle_components
+        .iter()
+        .zip(products)
+        .map(|(component, product)| match product {
+            Some(product) if !product.is_available() => Err(
+                BundleComponentError::ProductNotAvailable(component.product_id.clone()),
+            ),
+            Some(product) if !product.is_sellable_in(zone, country) => Err(
+                BundleComponentError::ProductNotSellable(component.product_id.clone()),
+            ),
+            Some(product) => Ok(BundleComponentItem::new(component, product)),
+            None => Err(BundleComponentError::ProductNotFound(
+                component.product_id.clone(),
+            )),
+        })
+        .collect()
+}
+
+// Aggregate rating of approved product reviews
+#[derive(Schema, Clone)]
+pub struct ProductRating {
+    pub average: f32,
+    pub count: u32,
+}
+
+impl ProductRating {
+    fn new() -> Self {
+        Self {
+            average: 0.0,
+            count: 0,
+        }
+    }
+}
+
+// Option dimension of a product, e.g. size with values S, M, L
+#[derive(Schema, Clone)]
+pub struct ProductOption {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Schema, Clone, Eq, PartialEq)]
+pub struct SelectedOption {
+    pub name: String,
+    pub value: String,
+}
+
+// Sellable variant (SKU) of a product, with one value selected for every option dimension
+#[derive(Schema, Clone)]
+pub struct ProductVariant {
+    pub variant_id: String,
+    pub options: Vec<SelectedOption>,
+}
+
+fn validate_variant_options(
+    options: &[ProductOption],
+    selected: &[SelectedOption],
+) -> Result<(), String> {
+    for option in options {
+        let values: Vec<&SelectedOption> =
+            selected.iter().filter(|s| s.name == option.name).collect();
+
+        match values.as_slice() {
+            [] => return Err(format!("Option {} not selected", option.name)),
+            [value] if !option.values.contains(&value.value) => {
+                return Err(format!(
+                    "Value {} not valid for option {}",
+                    value.value, option.name
+                ))
+            }
+            [_] => (),
+            _ => return Err(format!("Option {} selected more than once", option.name)),
+        }
+    }
+
+    if let Some(unknown) = selected
+        .iter()
+        .find(|s| !options.iter().any(|o| o.name == s.name))
+    {
+        return Err(format!("Unknown option {}", unknown.name));
+    }
+
+    Ok(())
+}
+
+fn same_options(a: &[SelectedOption], b: &[SelectedOption]) -> bool {
+    a.len() == b.len() && a.iter().all(|option| b.contains(option))
+}
+
+// Products are created as draft, published to active, archived to discontinued and restored to active
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ProductStatus {
+    Draft,
+    Active,
+    Discontinued,
+}
+
+impl ProductStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProductStatus::Draft => "draft",
+            ProductStatus::Active => "active",
+            ProductStatus::Discontinued => "discontinued",
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct UpdateProduct {
+    pub name: Option<String>,
+    pub brand: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Schema, Clone)]
+pub struct ProductNotFoundError {
+    pub message: String,
+    pub product_id: String,
+}
+
+impl ProductNotFoundError {
+    fn new(product_id: String) -> ProductNotFoundError {
+        ProductNotFoundError {
+            message: "Product not found".to_string(),
+            product_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ActionNotAllowedError {
+    pub message: String,
+    pub status: ProductStatus,
+}
+
+impl ActionNotAllowedError {
+    fn new(status: ProductStatus) -> ActionNotAllowedError {
+        ActionNotAllowedError {
+            message: "Can not update product with status".to_string(),
+            status,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct VariantNotFoundError {
+    pub message: String,
+    pub variant_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct VariantNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct AttributeNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum UpdateProductError {
+    ProductNotFound(ProductNotFoundError),
+    AttributeNotValid(AttributeNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum PublishProductError {
+    ProductNotFound(ProductNotFoundError),
+    ActionNotAllowed(ActionNotAllowedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum ArchiveProductError {
+    ProductNotFound(ProductNotFoundError),
+    ActionNotAllowed(ActionNotAllowedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RestoreProductError {
+    ProductNotFound(ProductNotFoundError),
+    ActionNotAllowed(ActionNotAllowedError),
+}
+
+#[derive(Schema, Clone)]
+pub enum UpdateOptionsError {
+    ProductNotFound(ProductNotFoundError),
+    VariantNotValid(VariantNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum AddVariantError {
+    ProductNotFound(ProductNotFoundError),
+    VariantNotValid(VariantNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RemoveVariantError {
+    ProductNotFound(ProductNotFoundError),
+    VariantNotFound(VariantNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub enum AssignCategoriesError {
+    ProductNotFound(ProductNotFoundError),
+    CategoryNotFound(CategoryNotFoundError),
+    AttributeNotValid(AttributeNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum UpdateAttributesError {
+    ProductNotFound(ProductNotFoundError),
+    AttributeNotValid(AttributeNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub struct MediaNotFoundError {
+    pub message: String,
+    pub media_id: String,
+}
+
+impl MediaNotFoundError {
+    fn new(media_id: String) -> MediaNotFoundError {
+        MediaNotFoundError {
+            message: "Media not found".to_string(),
+            media_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct MediaNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct MediaStorageError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum UploadMediaError {
+    ProductNotFound(ProductNotFoundError),
+    MediaNotValid(MediaNotValidError),
+    MediaStorage(MediaStorageError),
+}
+
+#[derive(Schema, Clone)]
+pub enum ReorderMediaError {
+    ProductNotFound(ProductNotFoundError),
+    MediaNotValid(MediaNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum SetPrimaryMediaError {
+    ProductNotFound(ProductNotFoundError),
+    MediaNotValid(MediaNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum DeleteMediaError {
+    ProductNotFound(ProductNotFoundError),
+    MediaNotFound(MediaNotFoundError),
+    MediaStorage(MediaStorageError),
+}
+
+#[derive(Schema, Clone)]
+pub struct LocaleNotValidError {
+    pub message: String,
+    pub locale: String,
+}
+
+impl LocaleNotValidError {
+    fn new(locale: String) -> LocaleNotValidError {
+        LocaleNotValidError {
+            message: "Locale not valid".to_string(),
+            locale,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct TranslationNotFoundError {
+    pub message: String,
+    pub locale: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum UpdateTranslationError {
+    ProductNotFound(ProductNotFoundError),
+    LocaleNotValid(LocaleNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RemoveTranslationError {
+    ProductNotFound(ProductNotFoundError),
+    TranslationNotFound(TranslationNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub struct LinkNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct LinkNotFoundError {
+    pub message: String,
+    pub product_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum AddLinkError {
+    ProductNotFound(ProductNotFoundError),
+    LinkedProductNotFound(ProductNotFoundError),
+    LinkNotValid(LinkNotValidError),
+}
+
+#[derive(Schema, Clone)]
+pub enum RemoveLinkError {
+    ProductNotFound(ProductNotFoundError),
+    LinkNotFound(LinkNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub struct BundleNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum UpdateBundleComponentsError {
+    ProductNotFound(ProductNotFoundError),
+    ComponentNotFound(ProductNotFoundError),
+    BundleNotValid(BundleNotValidError),
+}
+
+// Attribute definitions of the categories and tags,
+// schemas of parent categories apply to their descendants as well
+async fn get_attribute_definitions(
+    category_ids: &[String],
+    tags: Vec<String>,
+) -> Vec<AttributeDefinition> {
+    let category_client = CategoryAgentClient::get();
+    let paths = join_all(
+        category_ids
+            .iter()
+            .map(|id| category_client.get_category_path(id.clone())),
+    )
+    .await;
+
+    let mut schema_category_ids: Vec<String> = Vec::new();
+    for category in paths.into_iter().flat_map(|path| path.into_iter().rev()) {
+        if !schema_category_ids.contains(&category.category_id) {
+            schema_category_ids.push(category.category_id);
+        }
+    }
+
+    AttributeSchemaAgentClient::get()
+        .get_definitions(schema_category_ids, tags)
+        .await
+}
+
+// Validate the attributes for changed categories or tags, required attributes are checked
+// on attribute updates only, so categories can be assigned before the attributes are set
+async fn revalidate_attributes(
+    category_ids: &[String],
+    tags: Vec<String>,
+    attributes: Vec<ProductAttribute>,
+) -> Result<Vec<ProductAttribute>, AttributeNotValidError> {
+    if attributes.is_empty() {
+        return Ok(attributes);
+    }
+
+    let definitions = get_attribute_definitions(category_ids, tags).await;
+    validate_attribute_values(&definitions, attributes)
+        .map_err(|message| AttributeNotValidError { message })
+}
+
 #[agent_definition]
 trait ProductAgent {
     fn new(id: String) -> Self;
 
     fn get_product(&self) -> Option<Product>;
 
-    fn initialize_product(
+    fn get_localized_product(&self, locale: String) -> Option<Product>;
+
+    async fn initialize_product(
         &mut self,
         name: String,
         brand: String,
         description: String,
         tags: Vec<String>,
-    );
+    ) -> Result<(), UpdateProductError>;
+
+    async fn update_product(&mut self, data: UpdateProduct) -> Result<(), UpdateProductError>;
+
+    fn publish_product(&mut self) -> Result<(), PublishProductError>;
+
+    fn archive_product(&mut self) -> Result<(), ArchiveProductError>;
+
+    fn restore_product(&mut self) -> Result<(), RestoreProductError>;
+
+    fn update_options(&mut self, options: Vec<ProductOption>) -> Result<(), UpdateOptionsError>;
+
+    fn add_variant(
+        &mut self,
+        variant_id: String,
+        options: Vec<SelectedOption>,
+    ) -> Result<(), AddVariantError>;
+
+    fn remove_variant(&mut self, variant_id: String) -> Result<(), RemoveVariantError>;
+
+    async fn assign_categories(
+        &mut self,
+        category_ids: Vec<String>,
+    ) -> Result<(), AssignCategoriesError>;
+
+    async fn update_attributes(
+        &mut self,
+        attributes: Vec<ProductAttribute>,
+    ) -> Result<(), UpdateAttributesError>;
+
+    fn update_rating(&mut self, rating: ProductRating) -> Result<(), UpdateProductError>;
+
+    async fn add_link(
+        &mut self,
+        product_id: String,
+        link_type: ProductLinkType,
+    ) -> Result<(), AddLinkError>;
+
+    async fn update_bundle_components(
+        &mut self,
+        components: Vec<BundleComponent>,
+    ) -> Result<(), UpdateBundleComponentsError>;
+
+    fn remove_link(
+        &mut self,
+        product_id: String,
+        link_type: ProductLinkType,
+    ) -> Result<(), RemoveLinkError>;
+
+    fn update_availability(
+        &mut self,
+        availability: ProductAvailability,
+    ) -> Result<(), UpdateProductError>;
+
+    fn update_translation(
+        &mut self,
+        locale: String,
+        data: UpdateTranslation,
+    ) -> Result<(), UpdateTranslationError>;
+
+    fn remove_translation(&mut self, locale: String) -> Result<(), RemoveTranslationError>;
+
+    fn get_media(&self) -> Vec<ProductMedia>;
+
+    fn get_media_content(&self, media_id: String) -> Option<MediaContent>;
+
+    fn upload_media(
+        &mut self,
+        media_id: String,
+        data: UploadMedia,
+    ) -> Result<ProductMedia, UploadMediaError>;
+
+    fn reorder_media(&mut self, media_ids: Vec<String>) -> Result<(), ReorderMediaError>;
+
+    fn set_primary_media(&mut self, media_id: String) -> Result<(), SetPrimaryMediaError>;
+
+    fn delete_media(&mut self, media_id: String) -> Result<(), DeleteMediaError>;
 }
 
 struct ProductAgentImpl {
@@ -44,22 +916,631 @@ impl ProductAgent for ProductAgentImpl {
         self.state.clone()
     }
 
-    fn initialize_product(
+    fn get_localized_product(&self, locale: String) -> Option<Product> {
+        self.state
+            .as_ref()
+            .map(|product| product.localized(&locale))
+    }
+
+    // Create the product as draft, an existing product is updated with the given content only,
+    // keeping its status, creation time and other data
+    async fn initialize_product(
         &mut self,
         name: String,
         brand: String,
         description: String,
         tags: Vec<String>,
-    ) {
+    ) -> Result<(), UpdateProductError> {
+        if self.state.is_some() {
+            println!("Updating existing product {}", self._id);
+            return self
+                .update_product(UpdateProduct {
+                    name: Some(name),
+                    brand: Some(brand),
+                    description: Some(description),
+                    tags: Some(tags),
+                })
+                .await;
+        }
+
         let now = chrono::Utc::now();
-        self.state = Some(Product {
+        let product = Product {
             product_id: self._id.clone(),
             name,
             brand,
             description,
             tags,
+            status: ProductStatus::Draft,
+            options: vec![],
+            variants: vec![],
+            category_ids: vec![],
+            attributes: vec![],
+            media: vec![],
+            rating: ProductRating::new(),
+            translations: vec![],
+            links: vec![],
+            bundle_components: vec![],
+            availability: ProductAvailability::default(),
             created_at: now,
             updated_at: now,
-        });
+        };
+        trigger_index_product(&product);
+        self.state = Some(product);
+        Ok(())
+    }
+
+    async fn update_product(&mut self, data: UpdateProduct) -> Result<(), UpdateProductError> {
+        println!("Updating product {}", self._id);
+
+        let product = match self.state.as_ref() {
+            Some(product) => product,
+            None => {
+                return Err(UpdateProductError::ProductNotFound(
+                    ProductNotFoundError::new(self._id.clone()),
+                ))
+            }
+        };
+
+        let attributes = match data.tags.as_ref() {
+            Some(tags) if *tags != product.tags => Some(
+                revalidate_attributes(
+                    &product.category_ids,
+                    tags.clone(),
+                    product.attributes.clone(),
+                )
+                .await
+                .map_err(UpdateProductError::AttributeNotValid)?,
+            ),
+            _ => None,
+        };
+
+        if let Some(product) = self.state.as_mut() {
+            product.update(data);
+            if let Some(attributes) = attributes {
+                product.set_attributes(attributes);
+            }
+            trigger_index_product(product);
+        }
+        Ok(())
+    }
+
+    fn publish_product(&mut self) -> Result<(), PublishProductError> {
+        println!("Publishing product {}", self._id);
+        match self.state.as_mut() {
+            Some(product) if product.status != ProductStatus::Draft => Err(
+                PublishProductError::ActionNotAllowed(ActionNotAllowedError::new(product.status)),
+            ),
+            Some(product) => {
+                product.set_status(ProductStatus::Active);
+                trigger_index_product(product);
+                Ok(())
+            }
+            None => Err(PublishProductError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn archive_product(&mut self) -> Result<(), ArchiveProductError> {
+        println!("Archiving product {}", self._id);
+        match self.state.as_mut() {
+            Some(product) if product.status == ProductStatus::Discontinued => Err(
+                ArchiveProductError::ActionNotAllowed(ActionNotAllowedError::new(product.status)),
+            ),
+            Some(product) => {
+                product.set_status(ProductStatus::Discontinued);
+                trigger_index_product(product);
+                Ok(())
+            }
+            None => Err(ArchiveProductError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn restore_product(&mut self) -> Result<(), RestoreProductError> {
+        println!("Restoring product {}", self._id);
+        match self.state.as_mut() {
+            Some(product) if product.status != ProductStatus::Discontinued => Err(
+                RestoreProductError::ActionNotAllowed(ActionNotAllowedError::new(product.status)),
+            ),
+            Some(product) => {
+                product.set_status(ProductStatus::Active);
+                trigger_index_product(product);
+                Ok(())
+            }
+            None => Err(RestoreProductError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn update_options(&mut self, options: Vec<ProductOption>) -> Result<(), UpdateOptionsError> {
+        println!("Updating options of product {}", self._id);
+        match self.state.as_mut() {
+            Some(product) => product.set_options(options).map_err(|e| {
+                UpdateOptionsError::VariantNotValid(VariantNotValidError { message: e })
+            }),
+            None => Err(UpdateOptionsError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn add_variant(
+        &mut self,
+        variant_id: String,
+        options: Vec<SelectedOption>,
+    ) -> Result<(), AddVariantError> {
+        println!("Adding variant {} to product {}", variant_id, self._id);
+        match self.state.as_mut() {
+            Some(product) if product.get_variant(&variant_id).is_some() => {
+                Err(AddVariantError::VariantNotValid(VariantNotValidError {
+                    message: format!("Variant {} already exists", variant_id),
+                }))
+            }
+            Some(product) => product
+                .add_variant(ProductVariant {
+                    variant_id,
+                    options,
+                })
+                .map_err(|e| AddVariantError::VariantNotValid(VariantNotValidError { message: e })),
+            None => Err(AddVariantError::ProductNotFound(ProductNotFoundError::new(
+                self._id.clone(),
+            ))),
+        }
+    }
+
+    fn remove_variant(&mut self, variant_id: String) -> Result<(), RemoveVariantError> {
+        println!("Removing variant {} from product {}", variant_id, self._id);
+        match self.state.as_mut() {
+            Some(product) => {
+                if product.remove_variant(&variant_id) {
+                    Ok(())
+                } else {
+                    Err(RemoveVariantError::VariantNotFound(VariantNotFoundError {
+                        message: "Variant not found".to_string(),
+                        variant_id,
+                    }))
+                }
+            }
+            None => Err(RemoveVariantError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    async fn assign_categories(
+        &mut self,
+        category_ids: Vec<String>,
+    ) -> Result<(), AssignCategoriesError> {
+        println!(
+            "Assigning categories {:?} to product {}",
+            category_ids, self._id
+        );
+
+        let (tags, attributes) = match self.state.as_ref() {
+            Some(product) => (product.tags.clone(), product.attributes.clone()),
+            None => {
+                return Err(AssignCategoriesError::ProductNotFound(
+                    ProductNotFoundError::new(self._id.clone()),
+                ))
+            }
+        };
+
+        let category_client = CategoryAgentClient::get();
+        let categories = join_all(
+            category_ids
+                .iter()
+                .map(|id| category_client.get_category(id.clone())),
+        )
+        .await;
+
+        if let Some((id, _)) = category_ids
+            .iter()
+            .zip(categories)
+            .find(|(_, category)| category.is_none())
+        {
+            return Err(AssignCategoriesError::CategoryNotFound(
+                CategoryNotFoundError::new(id.clone()),
+            ));
+        }
+
+        let attributes = revalidate_attributes(&category_ids, tags, attributes)
+            .await
+            .map_err(AssignCategoriesError::AttributeNotValid)?;
+
+        if let Some(product) = self.state.as_mut() {
+            product.set_category_ids(category_ids);
+            product.set_attributes(attributes);
+        }
+        Ok(())
+    }
+
+    async fn update_attributes(
+        &mut self,
+        attributes: Vec<ProductAttribute>,
+    ) -> Result<(), UpdateAttributesError> {
+        println!("Updating attributes of product {}", self._id);
+
+        let (category_ids, tags) = match self.state.as_ref() {
+            Some(product) => (product.category_ids.clone(), product.tags.clone()),
+            None => {
+                return Err(UpdateAttributesError::ProductNotFound(
+                    ProductNotFoundError::new(self._id.clone()),
+                ))
+            }
+        };
+
+        let definitions = get_attribute_definitions(&category_ids, tags).await;
+
+        let attributes = validate_attributes(&definitions, attributes).map_err(|message| {
+            UpdateAttributesError::AttributeNotValid(AttributeNotValidError { message })
+        })?;
+
+        if let Some(product) = self.state.as_mut() {
+            product.set_attributes(attributes);
+        }
+        Ok(())
+    }
+
+    fn update_availability(
+        &mut self,
+        availability: ProductAvailability,
+    ) -> Result<(), UpdateProductError> {
+        println!("Updating availability of product {}", self._id);
+        match self.state.as_mut() {
+            Some(product) => {
+                product.set_availability(availability.normalized());
+                Ok(())
+            }
+            None => Err(UpdateProductError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn update_rating(&mut self, rating: ProductRating) -> Result<(), UpdateProductError> {
+        println!(
+            "Updating rating of product {} - average: {} count: {}",
+            self._id, rating.average, rating.count
+        );
+
+        match self.state.as_mut() {
+            Some(product) => {
+                product.set_rating(rating);
+                trigger_index_product(product);
+                Ok(())
+            }
+            None => Err(UpdateProductError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    async fn add_link(
+        &mut self,
+        product_id: String,
+        link_type: ProductLinkType,
+    ) -> Result<(), AddLinkError> {
+        println!(
+            "Adding link to product {} to product {}",
+            product_id, self._id
+        );
+
+        if self.state.is_none() {
+            return Err(AddLinkError::ProductNotFound(ProductNotFoundError::new(
+                self._id.clone(),
+            )));
+        }
+
+        if product_id == self._id {
+            return Err(AddLinkError::LinkNotValid(LinkNotValidError {
+                message: "Product can not be linked to itself".to_string(),
+            }));
+        }
+
+        let linked_product = ProductAgentClient::get(product_id.clone())
+            .get_product()
+            .await;
+
+        if linked_product.is_none() {
+            return Err(AddLinkError::LinkedProductNotFound(
+                ProductNotFoundError::new(product_id),
+            ));
+        }
+
+        if let Some(product) = self.state.as_mut() {
+            product.add_link(ProductLink {
+                product_id,
+                link_type,
+            });
+        }
+        Ok(())
+    }
+
+    async fn update_bundle_components(
+        &mut self,
+        components: Vec<BundleComponent>,
+    ) -> Result<(), UpdateBundleComponentsError> {
+        println!("Updating bundle components of product {}", self._id);
+
+        if self.state.is_none() {
+            return Err(UpdateBundleComponentsError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            ));
+        }
+
+        let not_valid = |message: String| {
+            Err(UpdateBundleComponentsError::BundleNotValid(
+                BundleNotValidError { message },
+            ))
+        };
+
+        let mut product_ids: Vec<&str> = Vec::new();
+        for component in components.iter() {
+            if component.product_id == self._id {
+                return not_valid("Bundle can not contain itself".to_string());
+            }
+            if component.quantity == 0 {
+                return not_valid(format!(
+                    "Quantity of component {} is zero",
+                    component.product_id
+                ));
+            }
+            if product_ids.contains(&component.product_id.as_str()) {
+                return not_valid(format!(
+                    "Component {} listed more than once",
+                    component.product_id
+                ));
+            }
+            product_ids.push(component.product_id.as_str());
+        }
+
+        let clients: Vec<ProductAgentClient> = components
+            .iter()
+            .map(|c| ProductAgentClient::get(c.product_id.clone()))
+            .collect();
+
+        let products = join_all(clients.iter().map(|client| client.get_product())).await;
+
+        for (component, product) in components.iter().zip(products) {
+            match product {
+                Some(product) if product.is_bundle() => {
+                    return not_valid(format!("Component {} is a bundle", component.product_id));
+                }
+                Some(_) => (),
+                None => {
+                    return Err(UpdateBundleComponentsError::ComponentNotFound(
+                        ProductNotFoundError::new(component.product_id.clone()),
+                    ));
+                }
+            }
+        }
+
+        // components can not be bundles, so a product used in another bundle can not become one
+        if !components.is_empty() {
+            let Some(component_id) = get_component_id() else {
+                return not_valid("Component not found".to_string());
+            };
+            let product_id = self._id.clone();
+            let excluded = HashSet::from([product_id.clone()]);
+            let bundles = get_other_products(component_id, None, excluded, |p| {
+                p.bundle_components
+                    .iter()
+                    .any(|c| c.product_id == product_id)
+            })
+            .await;
+            if let Some(bundle) = bundles.first() {
+                return not_valid(format!(
+                    "Product is a component of bundle {}",
+                    bundle.product_id
+                ));
+            }
+        }
+
+        if let Some(product) = self.state.as_mut() {
+            product.set_bundle_components(components);
+        }
+        Ok(())
+    }
+
+    fn remove_link(
+        &mut self,
+        product_id: String,
+        link_type: ProductLinkType,
+    ) -> Result<(), RemoveLinkError> {
+        println!(
+            "Removing link to product {} from product {}",
+            product_id, self._id
+        );
+
+        let link = ProductLink {
+            product_id: product_id.clone(),
+            link_type,
+        };
+
+        match self.state.as_mut() {
+            Some(product) => {
+                if product.remove_link(&link) {
+                    Ok(())
+                } else {
+                    Err(RemoveLinkError::LinkNotFound(LinkNotFoundError {
+                        message: "Link not found".to_string(),
+                        product_id,
+                    }))
+                }
+            }
+            None => Err(RemoveLinkError::ProductNotFound(ProductNotFoundError::new(
+                self._id.clone(),
+            ))),
+        }
+    }
+
+    fn update_translation(
+        &mut self,
+        locale: String,
+        data: UpdateTranslation,
+    ) -> Result<(), UpdateTranslationError> {
+        println!("Updating translation {} of product {}", locale, self._id);
+
+        let locale = normalize_locale(&locale).ok_or(UpdateTranslationError::LocaleNotValid(
+            LocaleNotValidError::new(locale),
+        ))?;
+
+        match self.state.as_mut() {
+            Some(product) => {
+                product.set_translation(ProductTranslation {
+                    locale,
+                    name: data.name,
+                    description: data.description,
+                    tags: data.tags,
+                });
+                Ok(())
+            }
+            None => Err(UpdateTranslationError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn remove_translation(&mut self, locale: String) -> Result<(), RemoveTranslationError> {
+        println!("Removing translation {} of product {}", locale, self._id);
+
+        let normalized = normalize_locale(&locale).unwrap_or(locale.clone());
+
+        match self.state.as_mut() {
+            Some(product) => {
+                if product.remove_translation(&normalized) {
+                    Ok(())
+                } else {
+                    Err(RemoveTranslationError::TranslationNotFound(
+                        TranslationNotFoundError {
+                            message: "Translation not found".to_string(),
+                            locale,
+                        },
+                    ))
+                }
+            }
+            None => Err(RemoveTranslationError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn get_media(&self) -> Vec<ProductMedia> {
+        self.state
+            .as_ref()
+            .map(|product| product.media.clone())
+            .unwrap_or_default()
+    }
+
+    fn get_media_content(&self, media_id: String) -> Option<MediaContent> {
+        let media = self.state.as_ref()?.get_media(&media_id)?;
+
+        match read_media(&get_object_name(&self._id, &media_id)) {
+            Ok(data) => Some(MediaContent {
+                media_id,
+                content_type: media.content_type,
+                data: encode_media(&data),
+            }),
+            Err(e) => {
+                println!("Reading media {} failed: {}", media_id, e);
+                None
+            }
+        }
+    }
+
+    fn upload_media(
+        &mut self,
+        media_id: String,
+        data: UploadMedia,
+    ) -> Result<ProductMedia, UploadMediaError> {
+        println!("Uploading media {} to product {}", media_id, self._id);
+
+        if self.state.is_none() {
+            return Err(UploadMediaError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            ));
+        }
+
+        let content = decode_media(&data)
+            .map_err(|message| UploadMediaError::MediaNotValid(MediaNotValidError { message }))?;
+
+        write_media(&get_object_name(&self._id, &media_id), &content)
+            .map_err(|message| UploadMediaError::MediaStorage(MediaStorageError { message }))?;
+
+        let media = ProductMedia {
+            media_id,
+            kind: data.kind,
+            content_type: data.content_type,
+            alt_text: data.alt_text,
+            width: data.width,
+            height: data.height,
+            size: content.len() as u64,
+            primary: data.primary && data.kind == MediaKind::Image,
+            created_at: chrono::Utc::now(),
+        };
+
+        match self.state.as_mut() {
+            Some(product) => {
+                product.add_media(media.clone());
+                Ok(product.get_media(&media.media_id).unwrap_or(media))
+            }
+            None => Err(UploadMediaError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn reorder_media(&mut self, media_ids: Vec<String>) -> Result<(), ReorderMediaError> {
+        println!("Reordering media of product {}", self._id);
+
+        match self.state.as_mut() {
+            Some(product) => product.reorder_media(&media_ids).map_err(|message| {
+                ReorderMediaError::MediaNotValid(MediaNotValidError { message })
+            }),
+            None => Err(ReorderMediaError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn set_primary_media(&mut self, media_id: String) -> Result<(), SetPrimaryMediaError> {
+        println!("Setting primary media {} of product {}", media_id, self._id);
+
+        match self.state.as_mut() {
+            Some(product) => product.set_primary_media(&media_id).map_err(|message| {
+                SetPrimaryMediaError::MediaNotValid(MediaNotValidError { message })
+            }),
+            None => Err(SetPrimaryMediaError::ProductNotFound(
+                ProductNotFoundError::new(self._id.clone()),
+            )),
+        }
+    }
+
+    fn delete_media(&mut self, media_id: String) -> Result<(), DeleteMediaError> {
+        println!("Deleting media {} of product {}", media_id, self._id);
+
+        let product = match self.state.as_mut() {
+            Some(product) => product,
+            None => {
+                return Err(DeleteMediaError::ProductNotFound(
+                    ProductNotFoundError::new(self._id.clone()),
+                ))
+            }
+        };
+
+        if product.get_media(&media_id).is_none() {
+            return Err(DeleteMediaError::MediaNotFound(MediaNotFoundError::new(
+                media_id,
+            )));
+        }
+
+        delete_media(&get_object_name(&self._id, &media_id))
+            .map_err(|message| DeleteMediaError::MediaStorage(MediaStorageError { message }))?;
+
+        product.remove_media(&media_id);
+        Ok(())
     }
 }
@@ -0,0 +1,112 @@
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::HashMap;
+
+pub const FREQUENTLY_BOUGHT_TOGETHER_COUNT: u8 = 5;
+
+#[derive(Schema, Clone)]
+pub struct ProductAffinityItem {
+    pub product_id: String,
+    pub order_count: u32,
+}
+
+// Order co-occurrence of a product with other products
+#[derive(Schema, Clone)]
+pub struct ProductAffinity {
+    pub product_id: String,
+    pub order_count: u32,
+    pub co_occurrences: HashMap<String, u32>,
+    pub order_ids: Vec<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ProductAffinity {
+    fn new(product_id: String) -> Self {
+        Self {
+            product_id,
+            order_count: 0,
+            co_occurrences: HashMap::new(),
+            order_ids: vec![],
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    // Record products ordered together with this product, orders already recorded are skipped
+    fn record_order(&mut self, order_id: String, product_ids: Vec<String>) -> bool {
+        if self.order_ids.contains(&order_id) {
+            return false;
+        }
+
+        let mut product_ids = product_ids;
+        product_ids.sort();
+        product_ids.dedup();
+
+        for product_id in product_ids.into_iter().filter(|id| *id != self.product_id) {
+            *self.co_occurrences.entry(product_id).or_insert(0) += 1;
+        }
+
+        self.order_ids.push(order_id);
+        self.order_count += 1;
+        self.updated_at = chrono::Utc::now();
+        true
+    }
+
+    fn get_top_items(&self, count: usize) -> Vec<ProductAffinityItem> {
+        let mut items: Vec<ProductAffinityItem> = self
+            .co_occurrences
+            .iter()
+            .map(|(product_id, order_count)| ProductAffinityItem {
+                product_id: product_id.clone(),
+                order_count: *order_count,
+            })
+            .collect();
+
+        items.sort_by(|a, b| {
+            b.order_count
+                .cmp(&a.order_count)
+                .then(a.product_id.cmp(&b.product_id))
+        });
+
+        items.into_iter().take(count).collect()
+    }
+}
+
+#[agent_definition]
+trait ProductAffinityAgent {
+    fn new(id: String) -> Self;
+
+    fn get_frequently_bought_together(&self) -> Vec<ProductAffinityItem>;
+
+    fn record_order(&mut self, order_id: String, product_ids: Vec<String>);
+}
+
+struct ProductAffinityAgentImpl {
+    state: ProductAffinity,
+}
+
+#[agent_implementation]
+impl ProductAffinityAgent for ProductAffinityAgentImpl {
+    fn new(id: String) -> Self {
+        ProductAffinityAgentImpl {
+            state: ProductAffinity::new(id),
+        }
+    }
+
+    fn get_frequently_bought_together(&self) -> Vec<ProductAffinityItem> {
+        self.state
+            .get_top_items(FREQUENTLY_BOUGHT_TOGETHER_COUNT as usize)
+    }
+
+    fn record_order(&mut self, order_id: String, product_ids: Vec<String>) {
+        println!(
+            "Recording order {} for product {} affinity",
+            order_id, self.state.product_id
+        );
+
+        if !self.state.record_order(order_id.clone(), product_ids) {
+            println!(
+                "Order {} already recorded for product {}",
+                order_id, self.state.product_id
+            );
+        }
+    }
+}
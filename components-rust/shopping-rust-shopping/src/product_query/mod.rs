@@ -0,0 +1,75 @@
+use crate::common::parse_ids;
+use crate::product::{Product, ProductAgentClient};
+use futures::stream::{self, StreamExt};
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::HashSet;
+
+pub const PRODUCT_QUERY_CONCURRENCY: usize = 10;
+
+// Products in the order of the ids, with ids of products not found
+#[derive(Schema, Clone)]
+pub struct ProductBatch {
+    pub products: Vec<Product>,
+    pub missing_ids: Vec<String>,
+}
+
+// Products in the order of the ids, `None` for products not found
+pub async fn get_products(product_ids: &[String]) -> Vec<Option<Product>> {
+    stream::iter(product_ids.iter().cloned())
+        .map(|product_id| async move { ProductAgentClient::get(product_id).get_product().await })
+        .buffered(PRODUCT_QUERY_CONCURRENCY)
+        .collect()
+        .await
+}
+
+// Products of the ids, duplicate ids are looked up once
+async fn get_product_batch(product_ids: Vec<String>) -> ProductBatch {
+    let mut unique_ids: HashSet<String> = HashSet::new();
+    let product_ids: Vec<String> = product_ids
+        .into_iter()
+        .filter(|id| unique_ids.insert(id.clone()))
+        .collect();
+
+    let products = get_products(&product_ids).await;
+
+    let mut batch = ProductBatch {
+        products: vec![],
+        missing_ids: vec![],
+    };
+    for (product_id, product) in product_ids.into_iter().zip(products) {
+        match product {
+            Some(product) => batch.products.push(product),
+            None => batch.missing_ids.push(product_id),
+        }
+    }
+    batch
+}
+
+#[agent_definition(mode = "ephemeral")]
+trait ProductQueryAgent {
+    fn new() -> Self;
+
+    async fn get_products(&self, product_ids: Vec<String>) -> ProductBatch;
+
+    async fn get_by_ids(&self, ids: String) -> ProductBatch;
+}
+
+struct ProductQueryAgentImpl {}
+
+#[agent_implementation]
+impl ProductQueryAgent for ProductQueryAgentImpl {
+    fn new() -> Self {
+        ProductQueryAgentImpl {}
+    }
+
+    async fn get_products(&self, product_ids: Vec<String>) -> ProductBatch {
+        println!("Getting {} products", product_ids.len());
+        get_product_batch(product_ids).await
+    }
+
+    async fn get_by_ids(&self, ids: String) -> ProductBatch {
+        let product_ids = parse_ids(&ids);
+        println!("Getting {} products", product_ids.len());
+        get_product_batch(product_ids).await
+    }
+}
@@ -0,0 +1,381 @@
+use crate::cart::CartAgentClient;
+use crate::order::{OrderAgentClient, OrderStatus};
+use crate::product::{ProductAgentClient, ProductRating};
+use futures::future::join_all;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+
+pub const REVIEW_RATING_MIN: u8 = 1;
+pub const REVIEW_RATING_MAX: u8 = 5;
+
+#[derive(Schema, Clone, Copy, Eq, PartialEq)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Schema, Clone)]
+pub struct Review {
+    pub review_id: String,
+    pub user_id: String,
+    pub rating: u8,
+    pub title: String,
+    pub body: String,
+    pub verified_purchase: bool,
+    pub status: ReviewStatus,
+    pub helpful_votes: u32,
+    pub helpful_user_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Schema, Clone)]
+pub struct AddReview {
+    pub user_id: String,
+    pub rating: u8,
+    pub title: String,
+    pub body: String,
+}
+
+// Aggregate of approved reviews, `histogram` contains review counts for ratings 1 to 5
+#[derive(Schema, Clone)]
+pub struct ReviewSummary {
+    pub average: f32,
+    pub count: u32,
+    pub histogram: Vec<u32>,
+}
+
+impl ReviewSummary {
+    fn new(reviews: &[Review]) -> Self {
+        let mut histogram = vec![0; REVIEW_RATING_MAX as usize];
+        let mut total: u32 = 0;
+        let mut count: u32 = 0;
+
+        for review in reviews
+            .iter()
+            .filter(|r| r.status == ReviewStatus::Approved)
+        {
+            histogram[(review.rating - REVIEW_RATING_MIN) as usize] += 1;
+            total += review.rating as u32;
+            count += 1;
+        }
+
+        let average = if count > 0 {
+            total as f32 / count as f32
+        } else {
+            0.0
+        };
+
+        Self {
+            average,
+            count,
+            histogram,
+        }
+    }
+
+    fn to_product_rating(&self) -> ProductRating {
+        ProductRating {
+            average: self.average,
+            count: self.count,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ProductReviews {
+    pub product_id: String,
+    pub reviews: Vec<Review>,
+    pub summary: ReviewSummary,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ProductReviews {
+    fn new(product_id: String) -> Self {
+        Self {
+            product_id,
+            reviews: vec![],
+            summary: ReviewSummary::new(&[]),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn get_review_mut(&mut self, review_id: &str) -> Option<&mut Review> {
+        self.reviews.iter_mut().find(|r| r.review_id == review_id)
+    }
+
+    fn recalculate_summary(&mut self) {
+        self.summary = ReviewSummary::new(&self.reviews);
+        self.updated_at = chrono::Utc::now();
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ProductNotFoundError {
+    pub message: String,
+    pub product_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct ReviewNotFoundError {
+    pub message: String,
+    pub review_id: String,
+}
+
+impl ReviewNotFoundError {
+    fn new(review_id: String) -> ReviewNotFoundError {
+        ReviewNotFoundError {
+            message: "Review not found".to_string(),
+            review_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ReviewNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct ReviewAlreadyExistsError {
+    pub message: String,
+    pub review_id: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct VoteNotAllowedError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum AddReviewError {
+    ProductNotFound(ProductNotFoundError),
+    ReviewNotValid(ReviewNotValidError),
+    ReviewAlreadyExists(ReviewAlreadyExistsError),
+}
+
+#[derive(Schema, Clone)]
+pub enum ModerateReviewError {
+    ReviewNotFound(ReviewNotFoundError),
+}
+
+#[derive(Schema, Clone)]
+pub enum VoteReviewError {
+    ReviewNotFound(ReviewNotFoundError),
+    VoteNotAllowed(VoteNotAllowedError),
+}
+
+fn validate_review(data: &AddReview) -> Result<(), String> {
+    if !(REVIEW_RATING_MIN..=REVIEW_RATING_MAX).contains(&data.rating) {
+        return Err(format!(
+            "Rating has to be between {} and {}",
+            REVIEW_RATING_MIN, REVIEW_RATING_MAX
+        ));
+    }
+    if data.user_id.trim().is_empty() {
+        return Err("User id is empty".to_string());
+    }
+    if data.title.trim().is_empty() {
+        return Err("Title is empty".to_string());
+    }
+    Ok(())
+}
+
+// Purchase is verified, if any not cancelled order of the user contains the product
+async fn is_verified_purchase(user_id: String, product_id: &str) -> bool {
+    let order_ids = CartAgentClient::get(user_id).get_order_ids().await;
+
+    let clients: Vec<_> = order_ids.into_iter().map(OrderAgentClient::get).collect();
+
+    let tasks: Vec<_> = clients.iter().map(|client| client.get_order()).collect();
+
+    join_all(tasks).await.into_iter().flatten().any(|order| {
+        order.order_status != OrderStatus::Cancelled
+            && order.items.iter().any(|item| item.product_id == product_id)
+    })
+}
+
+#[agent_definition]
+trait ProductReviewsAgent {
+    fn new(id: String) -> Self;
+
+    fn get_reviews(&self) -> Vec<Review>;
+
+    fn get_pending_reviews(&self) -> Vec<Review>;
+
+    fn get_summary(&self) -> ReviewSummary;
+
+    async fn add_review(&mut self, data: AddReview) -> Result<Review, AddReviewError>;
+
+    fn moderate_review(
+        &mut self,
+        review_id: String,
+        status: ReviewStatus,
+    ) -> Result<(), ModerateReviewError>;
+
+    fn vote_helpful(&mut self, review_id: String, user_id: String) -> Result<(), VoteReviewError>;
+}
+
+struct ProductReviewsAgentImpl {
+    state: ProductReviews,
+}
+
+impl ProductReviewsAgentImpl {
+    // Propagate the aggregate rating to the product, so it can be used by search
+    fn update_product_rating(&self) {
+        ProductAgentClient::get(self.state.product_id.clone())
+            .trigger_update_rating(self.state.summary.to_product_rating());
+    }
+}
+
+#[agent_implementation]
+impl ProductReviewsAgent for ProductReviewsAgentImpl {
+    fn new(id: String) -> Self {
+        ProductReviewsAgentImpl {
+            state: ProductReviews::new(id),
+        }
+    }
+
+    fn get_reviews(&self) -> Vec<Review> {
+        let mut reviews: Vec<Review> = self
+            .state
+            .reviews
+            .iter()
+            .filter(|r| r.status == ReviewStatus::Approved)
+            .cloned()
+            .collect();
+        reviews.sort_by(|a, b| {
+            b.helpful_votes
+                .cmp(&a.helpful_votes)
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        reviews
+    }
+
+    fn get_pending_reviews(&self) -> Vec<Review> {
+        self.state
+            .reviews
+            .iter()
+            .filter(|r| r.status == ReviewStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    fn get_summary(&self) -> ReviewSummary {
+        self.state.summary.clone()
+    }
+
+    async fn add_review(&mut self, data: AddReview) -> Result<Review, AddReviewError> {
+        let product_id = self.state.product_id.clone();
+        println!(
+            "Adding review of product {} by user {}",
+            product_id, data.user_id
+        );
+
+        validate_review(&data)
+            .map_err(|message| AddReviewError::ReviewNotValid(ReviewNotValidError { message }))?;
+
+        if let Some(review) = self
+            .state
+            .reviews
+            .iter()
+            .find(|r| r.user_id == data.user_id)
+        {
+            return Err(AddReviewError::ReviewAlreadyExists(
+                ReviewAlreadyExistsError {
+                    message: "Review of user already exists".to_string(),
+                    review_id: review.review_id.clone(),
+                },
+            ));
+        }
+
+        let product = ProductAgentClient::get(product_id.clone())
+            .get_product()
+            .await;
+
+        if product.is_none() {
+            return Err(AddReviewError::ProductNotFound(ProductNotFoundError {
+                message: "Product not found".to_string(),
+                product_id,
+            }));
+        }
+
+        let verified_purchase = is_verified_purchase(data.user_id.clone(), &product_id).await;
+
+        let now = chrono::Utc::now();
+        let review = Review {
+            review_id: uuid::Uuid::new_v4().to_string(),
+            user_id: data.user_id,
+            rating: data.rating,
+            title: data.title,
+            body: data.body,
+            verified_purchase,
+            status: ReviewStatus::Pending,
+            helpful_votes: 0,
+            helpful_user_ids: vec![],
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.state.reviews.push(review.clone());
+        self.state.updated_at = now;
+        Ok(review)
+    }
+
+    fn moderate_review(
+        &mut self,
+        review_id: String,
+        status: ReviewStatus,
+    ) -> Result<(), ModerateReviewError> {
+        println!(
+            "Moderating review {} of product {}",
+            review_id, self.state.product_id
+        );
+
+        match self.state.get_review_mut(&review_id) {
+            Some(review) => {
+                review.status = status;
+                review.updated_at = chrono::Utc::now();
+            }
+            None => {
+                return Err(ModerateReviewError::ReviewNotFound(
+                    ReviewNotFoundError::new(review_id),
+                ))
+            }
+        }
+
+        self.state.recalculate_summary();
+        self.update_product_rating();
+        Ok(())
+    }
+
+    fn vote_helpful(&mut self, review_id: String, user_id: String) -> Result<(), VoteReviewError> {
+        println!(
+            "Voting review {} of product {} helpful by user {}",
+            review_id, self.state.product_id, user_id
+        );
+
+        let review = self
+            .state
+            .get_review_mut(&review_id)
+            .filter(|r| r.status == ReviewStatus::Approved)
+            .ok_or(VoteReviewError::ReviewNotFound(ReviewNotFoundError::new(
+                review_id,
+            )))?;
+
+        if review.user_id == user_id {
+            return Err(VoteReviewError::VoteNotAllowed(VoteNotAllowedError {
+                message: "Can not vote for own review".to_string(),
+            }));
+        }
+        if review.helpful_user_ids.contains(&user_id) {
+            return Err(VoteReviewError::VoteNotAllowed(VoteNotAllowedError {
+                message: "Review already voted by user".to_string(),
+            }));
+        }
+
+        review.helpful_user_ids.push(user_id);
+        review.helpful_votes = review.helpful_user_ids.len() as u32;
+        review.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+}
@@ -1,116 +1,1518 @@
+use crate::attribute::{AttributeSchemaAgentClient, AttributeValue};
+use crate::category::CategoryAgentClient;
+use crate::common::{fold_text, CURRENCY_DEFAULT, PRICING_ZONE_DEFAULT};
+use crate::pricing::EffectivePricingItem;
+use crate::pricing_query::get_effective_prices;
 use crate::product::{Product, ProductAgentClient};
+use crate::search_analytics::SearchAnalyticsAgentClient;
+use crate::search_dictionary::{SearchDictionary, SearchDictionaryAgentClient};
 use futures::future::join_all;
 use golem_rust::bindings::golem::api::host::{
     resolve_component_id, AgentAllFilter, AgentAnyFilter, AgentNameFilter, AgentPropertyFilter,
     GetAgents, StringFilterComparator,
 };
-use golem_rust::{agent_definition, agent_implementation};
-use regex::Regex;
-use std::collections::HashSet;
 use golem_rust::golem_wasm::ComponentId;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Comparison {
+    fn parse(op: &str) -> Option<Self> {
+        match op {
+            "=" => Some(Comparison::Eq),
+            ">" => Some(Comparison::Gt),
+            ">=" => Some(Comparison::Ge),
+            "<" => Some(Comparison::Lt),
+            "<=" => Some(Comparison::Le),
+            _ => None,
+        }
+    }
+
+    fn matches(&self, value: f64, other: f64) -> bool {
+        match self {
+            Comparison::Eq => value == other,
+            Comparison::Gt => value > other,
+            Comparison::Ge => value >= other,
+            Comparison::Lt => value < other,
+            Comparison::Le => value <= other,
+        }
+    }
+}
+
+// Fields supported by the query language, other fields are matched against product attributes
+const QUERY_FIELDS: [&str; 16] = [
+    "product-id",
+    "productid",
+    "name",
+    "brand",
+    "description",
+    "tag",
+    "tags",
+    "status",
+    "rating",
+    "price",
+    "on-sale",
+    "category",
+    "zone",
+    "country",
+    "sort",
+    "match",
+];
+
+// Query options, only allowed at the top level of the query
+const QUERY_OPTIONS: [&str; 2] = ["sort", "match"];
+
+// Min length of a term matched with one edit, e.g. `iphnoe` matches `iphone`
+pub const FUZZY_MIN_LENGTH: usize = 4;
+
+// Min length of a term matched with two edits
+pub const FUZZY_TWO_EDITS_MIN_LENGTH: usize = 8;
+
+// Simple English stemming of plural and verb suffixes, e.g. `laptops` to `laptop`
+fn stem_word(word: &str) -> String {
+    let len = word.chars().count();
+    let strip = |suffix: &str| word[..word.len() - suffix.len()].to_string();
+
+    if len > 4 && word.ends_with("ies") {
+        format!("{}y", strip("ies"))
+    } else if len > 4
+        && ["sses", "xes", "zes", "ches", "shes"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        strip("es")
+    } else if len > 5 && word.ends_with("ing") {
+        strip("ing")
+    } else if len > 4 && word.ends_with("ed") {
+        strip("ed")
+    } else if len > 3
+        && word.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        strip("s")
+    } else {
+        word.to_string()
+    }
+}
+
+// Edit distance with adjacent transpositions counted as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut distances: Vec<Vec<usize>> = (0..=a.len())
+        .map(|i| (0..=b.len()).map(|j| if i == 0 { j } else { i }).collect())
+        .collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+fn get_words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+// Count of the term in the normalized text, if the term is not found as is,
+// words with the same stem or, for longer terms, within the edit distance are counted
+fn term_frequency(text: &str, term: &str, exact: bool) -> usize {
+    let count = text.matches(term).count();
+
+    if count > 0 || exact || term.contains(|c: char| !c.is_alphanumeric()) {
+        return count;
+    }
+
+    let term_stem = stem_word(term);
+    let term_length = term.chars().count();
+    let max_edits = if term_length >= FUZZY_TWO_EDITS_MIN_LENGTH {
+        2
+    } else if term_length >= FUZZY_MIN_LENGTH {
+        1
+    } else {
+        0
+    };
+
+    get_words(text)
+        .filter(|word| {
+            let word_stem = stem_word(word);
+            word_stem == term_stem
+                || (max_edits > 0
+                    && word.chars().count().abs_diff(term_length) <= max_edits
+                    && edit_distance(word, term) <= max_edits)
+        })
+        .count()
+}
+
+// Folded and stemmed words of the text, used to look up synonyms and stop words
+fn get_term_key(text: &str) -> String {
+    get_words(&fold_text(text))
+        .map(stem_word)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Normalize the text for matching, whitespace is collapsed and text is folded unless matching is exact
+fn normalize_text(text: &str, exact: bool) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if exact {
+        text.to_lowercase()
+    } else {
+        fold_text(&text)
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct QuerySyntaxError {
+    pub message: String,
+    pub position: u32,
+}
+
+impl QuerySyntaxError {
+    fn new(message: String, position: usize) -> QuerySyntaxError {
+        QuerySyntaxError {
+            message,
+            position: position as u32,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct SearchFailedError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub enum SearchError {
+    QuerySyntax(QuerySyntaxError),
+    SearchFailed(SearchFailedError),
+}
+
+impl SearchError {
+    pub fn message(&self) -> String {
+        match self {
+            SearchError::QuerySyntax(e) => format!("{} at position {}", e.message, e.position),
+            SearchError::SearchFailed(e) => e.message.clone(),
+        }
+    }
+}
+
+// Check the query syntax, without resolving attribute fields
+pub fn validate_query(query: &str) -> Result<(), QuerySyntaxError> {
+    ProductQueryMatcher::new(query).map(|_| ())
+}
+
+// Quote a field value, so it can be used in a query
+pub fn quote_query_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum QueryTokenKind {
+    // Word, quoted parts are unescaped, e.g. `name:"red shoe"`
+    Word(String),
+    // Quoted phrase
+    Phrase(String),
+    And,
+    Or,
+    Not,
+    Minus,
+    LeftParen,
+    RightParen,
+}
+
+impl QueryTokenKind {
+    fn description(&self) -> String {
+        match self {
+            QueryTokenKind::Word(word) => format!("word {}", word),
+            QueryTokenKind::Phrase(phrase) => format!("phrase \"{}\"", phrase),
+            QueryTokenKind::And => "operator AND".to_string(),
+            QueryTokenKind::Or => "operator OR".to_string(),
+            QueryTokenKind::Not => "operator NOT".to_string(),
+            QueryTokenKind::Minus => "operator -".to_string(),
+            QueryTokenKind::LeftParen => "opening parenthesis".to_string(),
+            QueryTokenKind::RightParen => "closing parenthesis".to_string(),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
-struct ProductQueryMatcher {
-    terms: Vec<String>,
-    field_filters: Vec<(String, String)>,
+struct QueryToken {
+    kind: QueryTokenKind,
+    position: usize,
 }
 
-impl ProductQueryMatcher {
-    // Parse a simple query string into terms and field filters
-    fn new(query: &str) -> Self {
-        let mut terms = Vec::new();
-        let mut field_filters = Vec::new();
+// Query expression, expressions next to each other without an operator are combined with AND
+#[derive(Clone, Debug)]
+enum QueryExpr {
+    Term(String),
+    Phrase(String),
+    Field(String, String),
+    Comparison(String, Comparison, f64),
+    And(Vec<QueryExpr>),
+    Or(Vec<QueryExpr>),
+    Not(Box<QueryExpr>),
+}
 
-        let tokens = Self::tokenize(query);
+impl QueryExpr {
+    // Terms and phrases used for relevance, negated ones are not included
+    fn terms(&self) -> Vec<String> {
+        match self {
+            QueryExpr::Term(term) if term != "*" => vec![term.clone()],
+            QueryExpr::Phrase(phrase) => vec![phrase.clone()],
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) => {
+                exprs.iter().flat_map(|expr| expr.terms()).collect()
+            }
+            _ => vec![],
+        }
+    }
 
-        for part in tokens {
-            if let Some((field, value)) = part.split_once(':') {
-                field_filters.push((field.to_string(), value.to_string()));
-            } else {
-                terms.push(part.to_string());
+    fn uses_field(&self, name: &str) -> bool {
+        match self {
+            QueryExpr::Field(field, _) | QueryExpr::Comparison(field, _, _) => field == name,
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) => {
+                exprs.iter().any(|expr| expr.uses_field(name))
             }
+            QueryExpr::Not(expr) => expr.uses_field(name),
+            _ => false,
         }
+    }
+
+    fn fields(&self) -> Vec<(&str, &str)> {
+        match self {
+            QueryExpr::Field(field, value) => vec![(field.as_str(), value.as_str())],
+            QueryExpr::And(exprs) | QueryExpr::Or(exprs) => {
+                exprs.iter().flat_map(|expr| expr.fields()).collect()
+            }
+            QueryExpr::Not(expr) => expr.fields(),
+            _ => vec![],
+        }
+    }
+}
+
+// Split the query into tokens, handling quoted strings and escapes
+fn tokenize(query: &str) -> Result<Vec<QueryToken>, QuerySyntaxError> {
+    // Read a quoted string, the opening quote is already consumed
+    fn read_quoted(
+        chars: &mut std::iter::Peekable<std::iter::Enumerate<std::str::Chars>>,
+        start: usize,
+    ) -> Result<String, QuerySyntaxError> {
+        let mut value = String::new();
+        loop {
+            match chars.next() {
+                Some((_, '"')) => return Ok(value),
+                Some((position, '\\')) => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => {
+                        return Err(QuerySyntaxError::new(
+                            "Escape at the end of the query".to_string(),
+                            position,
+                        ))
+                    }
+                },
+                Some((_, c)) => value.push(c),
+                None => {
+                    return Err(QuerySyntaxError::new(
+                        "Missing closing quote".to_string(),
+                        start,
+                    ))
+                }
+            }
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().enumerate().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => QueryTokenKind::LeftParen,
+            ')' => QueryTokenKind::RightParen,
+            '"' => QueryTokenKind::Phrase(read_quoted(&mut chars, position)?),
+            '-' if chars
+                .peek()
+                .is_some_and(|(_, next)| !next.is_whitespace() && *next != ')') =>
+            {
+                QueryTokenKind::Minus
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                let mut next = Some((position, c));
+                while let Some((position, c)) = next {
+                    match c {
+                        '"' => {
+                            word.push_str(&read_quoted(&mut chars, position)?);
+                            quoted = true;
+                        }
+                        '\\' => match chars.next() {
+                            Some((_, c)) => word.push(c),
+                            None => {
+                                return Err(QuerySyntaxError::new(
+                                    "Escape at the end of the query".to_string(),
+                                    position,
+                                ))
+                            }
+                        },
+                        _ => word.push(c),
+                    }
+                    next = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '(' && *c != ')');
+                }
+
+                match word.as_str() {
+                    "AND" if !quoted => QueryTokenKind::And,
+                    "OR" if !quoted => QueryTokenKind::Or,
+                    "NOT" if !quoted => QueryTokenKind::Not,
+                    _ => QueryTokenKind::Word(word),
+                }
+            }
+        };
+        tokens.push(QueryToken { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+// Recursive descent parser of the query grammar:
+//
+// query      = or-expr
+// or-expr    = and-expr ("OR" and-expr)*
+// and-expr   = unary-expr (["AND"] unary-expr)*
+// unary-expr = ("NOT" | "-") unary-expr | primary
+// primary    = "(" or-expr ")" | phrase | field ":" value | field op number | term
+struct QueryParser {
+    tokens: Vec<QueryToken>,
+    index: usize,
+    end: usize,
+    comparison_regex: Regex,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&QueryToken> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<QueryToken> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn parse(&mut self) -> Result<QueryExpr, QuerySyntaxError> {
+        if self.tokens.is_empty() {
+            return Ok(QueryExpr::And(vec![]));
+        }
+
+        let expr = self.parse_or()?;
+
+        match self.peek() {
+            Some(token) => Err(QuerySyntaxError::new(
+                "Unexpected closing parenthesis".to_string(),
+                token.position,
+            )),
+            None => Ok(expr),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<QueryExpr, QuerySyntaxError> {
+        let mut exprs = vec![self.parse_and()?];
+
+        while self
+            .peek()
+            .is_some_and(|token| token.kind == QueryTokenKind::Or)
+        {
+            self.next();
+            exprs.push(self.parse_and()?);
+        }
+
+        if exprs.len() == 1 {
+            Ok(exprs.remove(0))
+        } else {
+            Ok(QueryExpr::Or(exprs))
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<QueryExpr, QuerySyntaxError> {
+        let mut exprs = vec![self.parse_unary()?];
+
+        while let Some(token) = self.peek() {
+            match token.kind {
+                QueryTokenKind::Or | QueryTokenKind::RightParen => break,
+                QueryTokenKind::And => {
+                    self.next();
+                    exprs.push(self.parse_unary()?);
+                }
+                _ => exprs.push(self.parse_unary()?),
+            }
+        }
+
+        if exprs.len() == 1 {
+            Ok(exprs.remove(0))
+        } else {
+            Ok(QueryExpr::And(exprs))
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryExpr, QuerySyntaxError> {
+        match self.peek().map(|token| &token.kind) {
+            Some(QueryTokenKind::Not) | Some(QueryTokenKind::Minus) => {
+                self.next();
+                Ok(QueryExpr::Not(Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<QueryExpr, QuerySyntaxError> {
+        match self.next() {
+            Some(QueryToken {
+                kind: QueryTokenKind::LeftParen,
+                position,
+            }) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(QueryToken {
+                        kind: QueryTokenKind::RightParen,
+                        ..
+                    }) => Ok(expr),
+                    _ => Err(QuerySyntaxError::new(
+                        "Missing closing parenthesis".to_string(),
+                        position,
+                    )),
+                }
+            }
+            Some(QueryToken {
+                kind: QueryTokenKind::Phrase(phrase),
+                ..
+            }) => Ok(QueryExpr::Phrase(phrase)),
+            Some(QueryToken {
+                kind: QueryTokenKind::Word(word),
+                position,
+            }) => self.parse_word(word, position),
+            Some(QueryToken {
+                kind: QueryTokenKind::RightParen,
+                position,
+            }) => Err(QuerySyntaxError::new(
+                "Unexpected closing parenthesis".to_string(),
+                position,
+            )),
+            Some(QueryToken { kind, position }) => Err(QuerySyntaxError::new(
+                format!("Unexpected {}", kind.description()),
+                position,
+            )),
+            None => Err(QuerySyntaxError::new(
+                "Unexpected end of the query".to_string(),
+                self.end,
+            )),
+        }
+    }
+
+    fn parse_word(&self, word: String, position: usize) -> Result<QueryExpr, QuerySyntaxError> {
+        if let Some(caps) = self.comparison_regex.captures(&word) {
+            let field = caps[1].to_lowercase();
+            let value = caps[3].parse::<f64>().map_err(|_| {
+                QuerySyntaxError::new(format!("Invalid number {}", &caps[3]), position)
+            })?;
+            return match Comparison::parse(&caps[2]) {
+                Some(_)
+                    if field != "rating"
+                        && field != "price"
+                        && QUERY_FIELDS.contains(&field.as_str()) =>
+                {
+                    Err(QuerySyntaxError::new(
+                        format!("Field {} does not support comparison", field),
+                        position,
+                    ))
+                }
+                Some(comparison) => Ok(QueryExpr::Comparison(field, comparison, value)),
+                None => Err(QuerySyntaxError::new(
+                    format!("Invalid comparison {}", &caps[2]),
+                    position,
+                )),
+            };
+        }
+
+        match word.split_once(':') {
+            Some(("", _)) => Err(QuerySyntaxError::new(
+                "Missing field name".to_string(),
+                position,
+            )),
+            Some((field, "")) => Err(QuerySyntaxError::new(
+                format!("Missing value of field {}", field),
+                position,
+            )),
+            Some((field, _)) if QUERY_OPTIONS.contains(&field.to_lowercase().as_str()) => {
+                Err(QuerySyntaxError::new(
+                    format!(
+                        "Option {} is only allowed at the top level of the query",
+                        field.to_lowercase()
+                    ),
+                    position,
+                ))
+            }
+            Some((field, value))
+                if field.eq_ignore_ascii_case("on-sale")
+                    && value != "*"
+                    && value.parse::<bool>().is_err() =>
+            {
+                Err(QuerySyntaxError::new(
+                    format!(
+                        "Invalid value {} of field on-sale, expected true or false",
+                        value
+                    ),
+                    position,
+                ))
+            }
+            Some((field, value)) => Ok(QueryExpr::Field(field.to_lowercase(), value.to_string())),
+            None => Ok(QueryExpr::Term(word)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SearchSort {
+    Relevance,
+    Name,
+    Price,
+    Newest,
+    Rating,
+}
+
+impl SearchSort {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "relevance" => Some(SearchSort::Relevance),
+            "name" => Some(SearchSort::Name),
+            "price" => Some(SearchSort::Price),
+            "newest" => Some(SearchSort::Newest),
+            "rating" => Some(SearchSort::Rating),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct ProductSearchHit {
+    pub product: Product,
+    pub score: f32,
+    pub price: Option<EffectivePricingItem>,
+}
+
+// Options of a search, prices are in the currency and pricing zone, defaults are used if not set,
+// products not sellable in the zone are filtered out if the zone is set,
+// customer searches are tracked in search analytics, only the first page is recorded,
+// price facets need prices of all matching products and are only computed if requested
+#[derive(Schema, Clone, Default)]
+pub struct SearchOptions {
+    pub currency: Option<String>,
+    pub zone: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub track: Option<bool>,
+    pub price_facets: Option<bool>,
+}
+
+pub const SEARCH_LIMIT_DEFAULT: u32 = 20;
+
+pub const SEARCH_LIMIT_MAX: u32 = 100;
+
+// Max count of values of a facet
+pub const SEARCH_FACET_LIMIT: usize = 20;
+
+// Lower bounds of price facet buckets, the last bucket has no upper bound
+pub const SEARCH_PRICE_FACET_BUCKETS: [f32; 6] = [0.0, 25.0, 50.0, 100.0, 250.0, 500.0];
+
+// Facet value count, `filter` is the query filter selecting the value
+#[derive(Schema, Clone)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: u32,
+    pub filter: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct PriceFacetCount {
+    pub from: f32,
+    pub to: Option<f32>,
+    pub count: u32,
+    pub filter: String,
+}
+
+// Facets over all matching products, prices are in the currency and pricing zone of the search,
+// price facets are empty unless requested
+#[derive(Schema, Clone)]
+pub struct ProductSearchFacets {
+    pub brands: Vec<FacetCount>,
+    pub tags: Vec<FacetCount>,
+    pub categories: Vec<FacetCount>,
+    pub prices: Vec<PriceFacetCount>,
+}
+
+fn get_facet_counts<'a>(values: impl Iterator<Item = &'a String>, field: &str) -> Vec<FacetCount> {
+    let mut counts: HashMap<&String, u32> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+
+    let mut facets: Vec<FacetCount> = counts
+        .into_iter()
+        .map(|(value, count)| FacetCount {
+            value: value.clone(),
+            count,
+            filter: format!("{}:{}", field, quote_query_value(value)),
+        })
+        .collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+    facets.truncate(SEARCH_FACET_LIMIT);
+    facets
+}
+
+// Price facets are only computed if prices are given
+fn get_facets(
+    products: &[Product],
+    prices: Option<&HashMap<String, EffectivePricingItem>>,
+) -> ProductSearchFacets {
+    let mut price_counts = [0u32; SEARCH_PRICE_FACET_BUCKETS.len()];
+    for price in prices
+        .iter()
+        .flat_map(|prices| products.iter().filter_map(|p| prices.get(&p.product_id)))
+    {
+        if let Some(i) = SEARCH_PRICE_FACET_BUCKETS
+            .iter()
+            .rposition(|from| price.price >= *from)
+        {
+            price_counts[i] += 1;
+        }
+    }
+
+    let price_facets = SEARCH_PRICE_FACET_BUCKETS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| price_counts[*i] > 0)
+        .map(|(i, from)| {
+            let to = SEARCH_PRICE_FACET_BUCKETS.get(i + 1).copied();
+            let filter = match to {
+                Some(to) => format!("price>={} price<{}", from, to),
+                None => format!("price>={}", from),
+            };
+            PriceFacetCount {
+                from: *from,
+                to,
+                count: price_counts[i],
+                filter,
+            }
+        })
+        .collect();
+
+    ProductSearchFacets {
+        brands: get_facet_counts(products.iter().map(|p| &p.brand), "brand"),
+        tags: get_facet_counts(products.iter().flat_map(|p| p.tags.iter()), "tag"),
+        categories: get_facet_counts(
+            products.iter().flat_map(|p| p.category_ids.iter()),
+            "category",
+        ),
+        prices: price_facets,
+    }
+}
+
+// Effective prices of the products in the currency and pricing zone
+async fn get_product_prices(
+    products: &[Product],
+    currency: &str,
+    zone: &str,
+) -> HashMap<String, EffectivePricingItem> {
+    if products.is_empty() {
+        return HashMap::new();
+    }
+
+    get_effective_prices(
+        products.iter().map(|p| p.product_id.clone()).collect(),
+        currency.to_string(),
+        zone.to_string(),
+    )
+    .await
+}
+
+// Page of search hits, with the total count and facets of matching products,
+// `search-id` identifies the recorded search of tracked searches
+#[derive(Schema, Clone)]
+pub struct ProductSearchResult {
+    pub search_id: Option<String>,
+    pub hits: Vec<ProductSearchHit>,
+    pub facets: ProductSearchFacets,
+    pub total: u32,
+    pub limit: u32,
+    pub offset: u32,
+    pub next_offset: Option<u32>,
+}
+
+impl ProductSearchResult {
+    fn new(
+        hits: Vec<ProductSearchHit>,
+        facets: ProductSearchFacets,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Self {
+        let total = hits.len() as u32;
+        // Limit of at least one, so paging by the next offset always progresses
+        let limit = limit
+            .unwrap_or(SEARCH_LIMIT_DEFAULT)
+            .clamp(1, SEARCH_LIMIT_MAX);
+        let offset = offset.unwrap_or(0);
+        let end = offset.saturating_add(limit);
 
         Self {
-            terms,
-            field_filters,
+            search_id: None,
+            hits: hits
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect(),
+            facets,
+            total,
+            limit,
+            offset,
+            next_offset: if end < total { Some(end) } else { None },
+        }
+    }
+}
+
+const RELEVANCE_K1: f32 = 1.2;
+const RELEVANCE_B: f32 = 0.75;
+
+// Boosts of the name, brand, tags and description fields
+const RELEVANCE_FIELD_BOOSTS: [f32; 4] = [3.0, 2.0, 1.5, 1.0];
+
+// BM25 relevance of the products for the search terms, the result set is the document collection,
+// each field is scored with its own length normalization and weighted by the field boost
+fn get_relevance_scores(terms: &[String], products: &[Product], exact: bool) -> Vec<f32> {
+    let normalize = |text: &str| normalize_text(text, exact);
+
+    let mut scores = vec![0f32; products.len()];
+
+    if terms.is_empty() || products.is_empty() {
+        return scores;
+    }
+
+    let documents: Vec<[String; 4]> = products
+        .iter()
+        .map(|p| {
+            [
+                normalize(&p.name),
+                normalize(&p.brand),
+                normalize(&p.tags.join(" ")),
+                normalize(&p.description),
+            ]
+        })
+        .collect();
+
+    let lengths: Vec<[f32; 4]> = documents
+        .iter()
+        .map(|fields| fields.clone().map(|f| f.split(' ').count() as f32))
+        .collect();
+
+    let count = documents.len() as f32;
+    let mut average_lengths = [0f32; 4];
+    for (i, average_length) in average_lengths.iter_mut().enumerate() {
+        *average_length = (lengths.iter().map(|l| l[i]).sum::<f32>() / count).max(1.0);
+    }
+
+    for term in terms.iter().map(|term| normalize(term)) {
+        let term_frequencies: Vec<[f32; 4]> = documents
+            .iter()
+            .map(|fields| {
+                fields
+                    .clone()
+                    .map(|f| term_frequency(&f, &term, exact) as f32)
+            })
+            .collect();
+
+        let frequency = term_frequencies
+            .iter()
+            .filter(|tfs| tfs.iter().any(|tf| *tf > 0.0))
+            .count() as f32;
+        let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+        for ((tfs, lengths), score) in term_frequencies
+            .iter()
+            .zip(lengths.iter())
+            .zip(scores.iter_mut())
+        {
+            for (i, tf) in tfs.iter().enumerate() {
+                let tf = *tf;
+                if tf > 0.0 {
+                    let norm = 1.0 - RELEVANCE_B + RELEVANCE_B * lengths[i] / average_lengths[i];
+                    *score += RELEVANCE_FIELD_BOOSTS[i] * idf * (tf * (RELEVANCE_K1 + 1.0))
+                        / (tf + RELEVANCE_K1 * norm);
+                }
+            }
         }
     }
 
-    // Tokenize the query string, handling quoted strings
-    fn tokenize(query: &str) -> Vec<String> {
-        let mut tokens = Vec::new();
-        let mut current = String::new();
-        let mut in_quotes = false;
+    scores
+}
+
+#[derive(Clone, Debug)]
+struct ProductQueryMatcher {
+    expr: QueryExpr,
+    sort: SearchSort,
+    // Terms are matched as is, without diacritic folding, stemming and fuzzy matching
+    exact: bool,
+    // Fields which are not query fields, with the position in the query
+    attribute_fields: Vec<(String, usize)>,
+    categories: HashMap<String, HashSet<String>>,
+}
+
+impl ProductQueryMatcher {
+    // Parse the query string into the query expression and options
+    fn new(query: &str) -> Result<Self, QuerySyntaxError> {
+        let tokens = tokenize(query)?;
 
-        for c in query.chars() {
-            match c {
-                ' ' if !in_quotes => {
-                    if !current.is_empty() {
-                        tokens.push(current.trim().to_string());
-                        current.clear();
+        // `sort:` and `match:` options are taken from the top level of the query
+        let mut sort = SearchSort::Relevance;
+        let mut exact = false;
+        let mut query_tokens: Vec<QueryToken> = Vec::new();
+        let mut depth = 0;
+        for token in tokens {
+            match &token.kind {
+                QueryTokenKind::LeftParen => depth += 1,
+                QueryTokenKind::RightParen => depth -= 1,
+                QueryTokenKind::Word(word) if depth == 0 => {
+                    let negated = query_tokens.last().is_some_and(|last| {
+                        matches!(last.kind, QueryTokenKind::Not | QueryTokenKind::Minus)
+                    });
+                    match word.split_once(':') {
+                        Some((field, value)) if !negated && !value.is_empty() => {
+                            match field.to_lowercase().as_str() {
+                                "sort" => {
+                                    sort = SearchSort::parse(&value.to_lowercase()).ok_or(
+                                        QuerySyntaxError::new(
+                                            format!("Unknown sort {}", value),
+                                            token.position,
+                                        ),
+                                    )?;
+                                    continue;
+                                }
+                                "match" => {
+                                    exact = match value.to_lowercase().as_str() {
+                                        "exact" => true,
+                                        "fuzzy" => false,
+                                        _ => {
+                                            return Err(QuerySyntaxError::new(
+                                                format!("Unknown match {}", value),
+                                                token.position,
+                                            ))
+                                        }
+                                    };
+                                    continue;
+                                }
+                                _ => (),
+                            }
+                        }
+                        _ => (),
                     }
                 }
-                '"' => {
-                    in_quotes = !in_quotes;
+                _ => (),
+            }
+            query_tokens.push(token);
+        }
+
+        let mut parser = QueryParser {
+            tokens: query_tokens,
+            index: 0,
+            end: query.chars().count(),
+            comparison_regex: Regex::new(r"^([\w-]+)(>=|<=|>|<|=)(-?[\d.]+)$")
+                .expect("valid comparison regex"),
+        };
+        let expr = parser.parse()?;
+
+        let mut attribute_fields: Vec<(String, usize)> = Vec::new();
+        for token in parser.tokens.iter() {
+            if let QueryTokenKind::Word(word) = &token.kind {
+                let field = parser
+                    .comparison_regex
+                    .captures(word)
+                    .map(|caps| caps[1].to_lowercase())
+                    .or_else(|| word.split_once(':').map(|(field, _)| field.to_lowercase()));
+
+                if let Some(field) = field {
+                    if !QUERY_FIELDS.contains(&field.as_str()) {
+                        attribute_fields.push((field, token.position));
+                    }
                 }
-                _ => {
-                    current.push(c);
+            }
+        }
+
+        Ok(Self {
+            expr,
+            sort,
+            exact,
+            attribute_fields,
+            categories: HashMap::new(),
+        })
+    }
+
+    // Rewrite terms and phrases, then remove stop words and expand terms and phrases
+    // with their synonyms, unless matching is exact
+    fn apply_dictionary(&mut self, dictionary: &SearchDictionary) {
+        self.apply_rewrites(dictionary);
+
+        if self.exact {
+            return;
+        }
+
+        let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+        for rule in dictionary.synonyms.iter() {
+            let mut group = vec![rule.term.clone()];
+            group.extend(rule.synonyms.iter().cloned());
+
+            let terms = if rule.two_way {
+                &group[..]
+            } else {
+                &group[..1]
+            };
+            for term in terms {
+                let expansions = synonyms.entry(get_term_key(term)).or_default();
+                for synonym in group.iter().filter(|s| *s != term) {
+                    if !expansions.contains(synonym) {
+                        expansions.push(synonym.clone());
+                    }
                 }
             }
         }
 
-        if !current.is_empty() {
-            tokens.push(current.trim().to_string());
+        let stop_words: HashSet<String> = dictionary
+            .stop_words
+            .iter()
+            .map(|word| get_term_key(word))
+            .collect();
+
+        self.expr = expand_expr(self.expr.clone(), &synonyms, &stop_words);
+    }
+
+    // Replace terms and phrases matching rewrite patterns with the parsed replacements,
+    // `sort:` and `match:` options of applied replacements are used for the query
+    fn apply_rewrites(&mut self, dictionary: &SearchDictionary) {
+        let rewrites: Vec<QueryRewrite> = dictionary
+            .rewrites
+            .iter()
+            .filter_map(|rule| match ProductQueryMatcher::new(&rule.replacement) {
+                Ok(replacement) => Some(QueryRewrite {
+                    words: rule.pattern.split_whitespace().map(get_term_key).collect(),
+                    replacement,
+                }),
+                Err(e) => {
+                    println!(
+                        "Invalid rewrite replacement {}: {}",
+                        rule.replacement, e.message
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        if rewrites.is_empty() {
+            return;
         }
 
-        tokens
+        let mut applied: Vec<usize> = Vec::new();
+        self.expr = rewrite_expr(self.expr.clone(), &rewrites, &mut applied);
+
+        for rewrite in applied.into_iter().map(|i| &rewrites[i]) {
+            if rewrite.replacement.sort != SearchSort::Relevance {
+                self.sort = rewrite.replacement.sort;
+            }
+            self.exact |= rewrite.replacement.exact;
+        }
+    }
+
+    // Category ids used in `category:` filters
+    fn category_filters(&self) -> Vec<String> {
+        let mut category_ids: Vec<String> = self
+            .expr
+            .fields()
+            .into_iter()
+            .filter(|(field, _)| *field == "category")
+            .map(|(_, value)| value.to_string())
+            .collect();
+        category_ids.sort();
+        category_ids.dedup();
+        category_ids
+    }
+
+    // Resolve `category:` filters to the category and all its descendants
+    async fn resolve_categories(&mut self) {
+        let category_ids = self.category_filters();
+
+        if !category_ids.is_empty() {
+            let client = CategoryAgentClient::get();
+            let descendants = join_all(
+                category_ids
+                    .iter()
+                    .map(|id| client.get_descendant_ids(id.clone())),
+            )
+            .await;
+
+            self.categories = category_ids
+                .into_iter()
+                .zip(descendants)
+                .map(|(id, ids)| (id, ids.into_iter().collect()))
+                .collect();
+        }
+    }
+
+    // Check that fields which are not query fields are defined in attribute schemas
+    async fn validate_attribute_fields(&self) -> Result<(), QuerySyntaxError> {
+        if self.attribute_fields.is_empty() {
+            return Ok(());
+        }
+
+        let attribute_names = AttributeSchemaAgentClient::get()
+            .get_attribute_names()
+            .await;
+
+        match self
+            .attribute_fields
+            .iter()
+            .find(|(field, _)| !attribute_names.contains(field))
+        {
+            Some((field, position)) => Err(QuerySyntaxError::new(
+                format!("Unknown field {}", field),
+                *position,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Query filters by price, prices of the products not matched without prices are needed
+    fn uses_prices(&self) -> bool {
+        self.expr.uses_field("price") || self.expr.uses_field("on-sale")
+    }
+
+    // Query filters or sorts by price, prices of all products are needed for the hits
+    fn uses_all_prices(&self) -> bool {
+        self.uses_prices() || self.sort == SearchSort::Price
+    }
+
+    // Only active products are returned, unless the query filters by status
+    fn status_matches(&self, product: &Product) -> bool {
+        product.is_available()
+            || self
+                .expr
+                .fields()
+                .iter()
+                .any(|(field, _)| *field == "status")
+    }
+
+    // Check if a product matches the query, prices are used for `price` and `on-sale` filters
+    fn matches(&self, product: &Product, prices: &HashMap<String, EffectivePricingItem>) -> bool {
+        self.status_matches(product) && self.expr_matches(&self.expr, product, prices)
+    }
+
+    // Check if a product matches the query without prices, `None` if it depends on price filters
+    fn matches_without_prices(&self, product: &Product) -> Option<bool> {
+        if self.status_matches(product) {
+            self.expr_matches_without_prices(&self.expr, product)
+        } else {
+            Some(false)
+        }
     }
 
-    // Check if a product matches the query
-    fn matches(&self, product: Product) -> bool {
-        fn text_matches(text: &str, query: &str) -> bool {
-            query == "*" || text.to_lowercase().contains(&query.to_lowercase())
+    fn expr_matches_without_prices(&self, expr: &QueryExpr, product: &Product) -> Option<bool> {
+        match expr {
+            QueryExpr::And(exprs) => {
+                let mut result = Some(true);
+                for expr in exprs {
+                    match self.expr_matches_without_prices(expr, product) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => (),
+                    }
+                }
+                result
+            }
+            QueryExpr::Or(exprs) => {
+                let mut result = Some(false);
+                for expr in exprs {
+                    match self.expr_matches_without_prices(expr, product) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => (),
+                    }
+                }
+                result
+            }
+            QueryExpr::Not(expr) => self
+                .expr_matches_without_prices(expr, product)
+                .map(|matches| !matches),
+            QueryExpr::Field(field, _) | QueryExpr::Comparison(field, _, _)
+                if field == "price" || field == "on-sale" =>
+            {
+                None
+            }
+            _ => Some(self.expr_matches(expr, product, &HashMap::new())),
         }
+    }
+
+    fn expr_matches(
+        &self,
+        expr: &QueryExpr,
+        product: &Product,
+        prices: &HashMap<String, EffectivePricingItem>,
+    ) -> bool {
+        let text_matches = |text: &str, query: &str| {
+            query == "*"
+                || term_frequency(
+                    &normalize_text(text, self.exact),
+                    &normalize_text(query, self.exact),
+                    self.exact,
+                ) > 0
+        };
 
         fn text_exact_matches(text: &str, query: &str) -> bool {
             query == "*" || text == query
         }
 
-        // Check field filters first
-        for (field, value) in self.field_filters.iter() {
-            let matches = match field.to_lowercase().as_str() {
+        // Phrase matches words in the same order, regardless of whitespace between them,
+        // words are compared by stem unless matching is exact
+        let phrase_matches = |text: &str, phrase: &str| {
+            let text = normalize_text(text, self.exact);
+            let phrase = normalize_text(phrase, self.exact);
+
+            if text.contains(&phrase) {
+                return true;
+            }
+            if self.exact {
+                return false;
+            }
+
+            let phrase_words: Vec<String> = get_words(&phrase).map(stem_word).collect();
+            let text_words: Vec<String> = get_words(&text).map(stem_word).collect();
+
+            !phrase_words.is_empty()
+                && text_words
+                    .windows(phrase_words.len())
+                    .any(|words| words == phrase_words.as_slice())
+        };
+
+        match expr {
+            QueryExpr::And(exprs) => exprs
+                .iter()
+                .all(|expr| self.expr_matches(expr, product, prices)),
+            QueryExpr::Or(exprs) => exprs
+                .iter()
+                .any(|expr| self.expr_matches(expr, product, prices)),
+            QueryExpr::Not(expr) => !self.expr_matches(expr, product, prices),
+            QueryExpr::Term(term) => {
+                text_matches(&product.name, term)
+                    || text_matches(&product.brand, term)
+                    || text_matches(&product.description, term)
+                    || product.tags.iter().any(|tag| text_matches(tag, term))
+            }
+            QueryExpr::Phrase(phrase) => {
+                phrase_matches(&product.name, phrase)
+                    || phrase_matches(&product.brand, phrase)
+                    || phrase_matches(&product.description, phrase)
+                    || product.tags.iter().any(|tag| phrase_matches(tag, phrase))
+            }
+            QueryExpr::Field(field, value) => match field.as_str() {
                 "product-id" | "productid" => text_exact_matches(&product.product_id, value),
                 "name" => text_matches(&product.name, value),
                 "brand" => text_matches(&product.brand, value),
                 "description" => text_matches(&product.description, value),
                 "tag" | "tags" => product.tags.iter().any(|tag| text_matches(tag, value)),
-                _ => false, // Unknown field
+                "status" => value == "*" || product.status.as_str().contains(&value.to_lowercase()),
+                "rating" => number_matches(product.rating.average as f64, value),
+                "price" => prices
+                    .get(&product.product_id)
+                    .is_some_and(|price| number_matches(price.price as f64, value)),
+                "on-sale" => {
+                    value == "*"
+                        || value.parse::<bool>().is_ok_and(|on_sale| {
+                            prices
+                                .get(&product.product_id)
+                                .is_some_and(|price| price.on_sale)
+                                == on_sale
+                        })
+                }
+                "category" => product.category_ids.iter().any(|id| {
+                    self.categories
+                        .get(value)
+                        .map_or(id == value, |ids| ids.contains(id))
+                }),
+                // Products not sellable in the zone or country are filtered out
+                "zone" => value == "*" || product.availability.is_zone_allowed(value),
+                "country" => value == "*" || product.availability.is_country_allowed(value),
+                // Other fields are matched against product attributes
+                _ => product
+                    .get_attribute(field)
+                    .is_some_and(|attribute| attribute_matches(&attribute.value, value)),
+            },
+            QueryExpr::Comparison(field, comparison, value) => {
+                if field == "rating" {
+                    comparison.matches(product.rating.average as f64, *value)
+                } else if field == "price" {
+                    prices
+                        .get(&product.product_id)
+                        .is_some_and(|price| comparison.matches(price.price as f64, *value))
+                } else {
+                    product
+                        .get_attribute(field)
+                        .is_some_and(|attribute| match &attribute.value {
+                            AttributeValue::Number(number) => {
+                                comparison.matches(number.value, *value)
+                            }
+                            _ => false,
+                        })
+                }
+            }
+        }
+    }
+
+    // Score the products and sort them by the `sort:` option, by relevance if not set
+    fn get_hits(
+        &self,
+        products: Vec<Product>,
+        prices: &HashMap<String, EffectivePricingItem>,
+    ) -> Vec<ProductSearchHit> {
+        let scores = get_relevance_scores(&self.expr.terms(), &products, self.exact);
+
+        let mut hits: Vec<ProductSearchHit> = products
+            .into_iter()
+            .zip(scores)
+            .map(|(product, score)| {
+                let price = prices.get(&product.product_id).cloned();
+                ProductSearchHit {
+                    product,
+                    score,
+                    price,
+                }
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            let (pa, pb) = (&a.product, &b.product);
+            let order = match self.sort {
+                SearchSort::Relevance => b.score.total_cmp(&a.score),
+                SearchSort::Name => pa.name.to_lowercase().cmp(&pb.name.to_lowercase()),
+                // Products without price are last
+                SearchSort::Price => match (prices.get(&pa.product_id), prices.get(&pb.product_id))
+                {
+                    (Some(a), Some(b)) => a.price.total_cmp(&b.price),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                },
+                SearchSort::Newest => pb.created_at.cmp(&pa.created_at),
+                SearchSort::Rating => pb
+                    .rating
+                    .average
+                    .total_cmp(&pa.rating.average)
+                    .then(pb.rating.count.cmp(&pa.rating.count)),
             };
+            order
+                .then(b.score.total_cmp(&a.score))
+                .then(pa.product_id.cmp(&pb.product_id))
+        });
 
-            if !matches {
-                return false;
+        hits
+    }
+}
+
+// Rewrite rule, the pattern words are compared by term key, like synonyms
+struct QueryRewrite {
+    words: Vec<String>,
+    replacement: ProductQueryMatcher,
+}
+
+impl QueryRewrite {
+    // Count of expressions matching the pattern at the start, a phrase matches the whole pattern,
+    // terms match the pattern words
+    fn matches(&self, exprs: &[QueryExpr]) -> Option<usize> {
+        match exprs.first() {
+            Some(QueryExpr::Phrase(phrase)) if get_term_key(phrase) == self.words.join(" ") => {
+                Some(1)
             }
+            _ if exprs.len() >= self.words.len()
+                && self.words.iter().zip(exprs).all(|(word, expr)| {
+                    matches!(expr, QueryExpr::Term(term) if get_term_key(term) == *word)
+                }) =>
+            {
+                Some(self.words.len())
+            }
+            _ => None,
         }
+    }
+}
 
-        // If no terms to match, just check if field filters passed
-        if self.terms.is_empty() {
-            return true;
+// Rewrite the expressions, the first matching rule is used, replacements are not rewritten again
+fn rewrite_exprs(
+    exprs: Vec<QueryExpr>,
+    rewrites: &[QueryRewrite],
+    applied: &mut Vec<usize>,
+) -> Vec<QueryExpr> {
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    while i < exprs.len() {
+        let rewrite = rewrites
+            .iter()
+            .enumerate()
+            .find_map(|(r, rewrite)| rewrite.matches(&exprs[i..]).map(|count| (r, count)));
+
+        match rewrite {
+            Some((r, count)) => {
+                result.push(rewrites[r].replacement.expr.clone());
+                applied.push(r);
+                i += count;
+            }
+            None => {
+                result.push(match &exprs[i] {
+                    QueryExpr::Term(_) | QueryExpr::Phrase(_) => exprs[i].clone(),
+                    expr => rewrite_expr(expr.clone(), rewrites, applied),
+                });
+                i += 1;
+            }
         }
+    }
 
-        // Check search terms against all searchable fields
-        for term in self.terms.iter() {
-            let matches = text_matches(&product.name, term)
-                || text_matches(&product.brand, term)
-                || text_matches(&product.description, term)
-                || product.tags.iter().any(|tag| text_matches(tag, term));
+    result
+}
 
-            if !matches {
-                return false;
+// Multi-word patterns match terms next to each other in the same AND expression
+fn rewrite_expr(expr: QueryExpr, rewrites: &[QueryRewrite], applied: &mut Vec<usize>) -> QueryExpr {
+    match expr {
+        QueryExpr::Term(_) | QueryExpr::Phrase(_) => rewrite_exprs(vec![expr], rewrites, applied)
+            .pop()
+            .expect("one expression"),
+        QueryExpr::And(exprs) => QueryExpr::And(rewrite_exprs(exprs, rewrites, applied)),
+        QueryExpr::Or(exprs) => QueryExpr::Or(
+            exprs
+                .into_iter()
+                .map(|expr| rewrite_expr(expr, rewrites, applied))
+                .collect(),
+        ),
+        QueryExpr::Not(expr) => QueryExpr::Not(Box::new(rewrite_expr(*expr, rewrites, applied))),
+        _ => expr,
+    }
+}
+
+fn expand_expr(
+    expr: QueryExpr,
+    synonyms: &HashMap<String, Vec<String>>,
+    stop_words: &HashSet<String>,
+) -> QueryExpr {
+    let expand = |expr: QueryExpr| expand_expr(expr, synonyms, stop_words);
+
+    match expr {
+        QueryExpr::Term(ref text) | QueryExpr::Phrase(ref text) => {
+            match synonyms.get(&get_term_key(text)) {
+                Some(expansions) => {
+                    let mut exprs = vec![expr.clone()];
+                    exprs.extend(expansions.iter().map(|synonym| {
+                        if synonym.contains(char::is_whitespace) {
+                            QueryExpr::Phrase(synonym.clone())
+                        } else {
+                            QueryExpr::Term(synonym.clone())
+                        }
+                    }));
+                    QueryExpr::Or(exprs)
+                }
+                None => expr,
+            }
+        }
+        // Stop words are removed, unless the query has only stop words
+        QueryExpr::And(exprs) => {
+            let is_stop_word = |expr: &QueryExpr| matches!(expr, QueryExpr::Term(term) if stop_words.contains(&get_term_key(term)));
+            let exprs = if exprs.iter().all(is_stop_word) {
+                exprs
+            } else {
+                exprs
+                    .into_iter()
+                    .filter(|expr| !is_stop_word(expr))
+                    .collect()
+            };
+            match exprs.len() {
+                1 => expand(exprs.into_iter().next().expect("one expression")),
+                _ => QueryExpr::And(exprs.into_iter().map(expand).collect()),
             }
         }
+        QueryExpr::Or(exprs) => QueryExpr::Or(exprs.into_iter().map(expand).collect()),
+        QueryExpr::Not(expr) => QueryExpr::Not(Box::new(expand(*expr))),
+        _ => expr,
+    }
+}
 
-        true
+// Match number, supports ranges like `13..15`, `13..` or `..15` and comparisons like `<15`
+fn number_matches(number: f64, query: &str) -> bool {
+    for op in [">=", "<=", ">", "<", "="] {
+        if let Some(value) = query.strip_prefix(op) {
+            return match (Comparison::parse(op), value.parse::<f64>()) {
+                (Some(comparison), Ok(value)) => comparison.matches(number, value),
+                _ => false,
+            };
+        }
+    }
+
+    match query.split_once("..") {
+        Some((from, to)) => {
+            let from = if from.is_empty() {
+                Some(f64::MIN)
+            } else {
+                from.parse::<f64>().ok()
+            };
+            let to = if to.is_empty() {
+                Some(f64::MAX)
+            } else {
+                to.parse::<f64>().ok()
+            };
+            match (from, to) {
+                (Some(from), Some(to)) => number >= from && number <= to,
+                _ => false,
+            }
+        }
+        None => query
+            .parse::<f64>()
+            .is_ok_and(|query_value| query_value == number),
+    }
+}
+
+// Match attribute value
+fn attribute_matches(attribute: &AttributeValue, query: &str) -> bool {
+    if query == "*" {
+        return true;
+    }
+
+    match attribute {
+        AttributeValue::Text(text) => text.to_lowercase().contains(&query.to_lowercase()),
+        AttributeValue::Enum(value) => value.eq_ignore_ascii_case(query),
+        AttributeValue::Boolean(value) => query
+            .parse::<bool>()
+            .is_ok_and(|query_value| query_value == *value),
+        AttributeValue::Number(number) => number_matches(number.value, query),
     }
 }
 
@@ -133,10 +1535,50 @@ fn get_product_agent_id(agent_name: &str) -> Option<String> {
         .map(|caps| caps[1].to_string())
 }
 
-async fn get_products(
-    agent_ids: HashSet<String>,
-    matcher: ProductQueryMatcher,
-) -> Result<Vec<Product>, String> {
+pub fn get_component_id() -> Option<ComponentId> {
+    resolve_component_id("shopping-rust:shopping")
+}
+
+// All products matching the filter, the filter is applied to each page of the product agents scan
+pub async fn get_all_products(
+    component_id: ComponentId,
+    locale: Option<String>,
+    filter: impl Fn(&Product) -> bool,
+) -> Vec<Product> {
+    get_other_products(component_id, locale, HashSet::new(), filter).await
+}
+
+// All products matching the filter except the excluded ones,
+// a product agent has to exclude itself, as it can not call itself
+pub async fn get_other_products(
+    component_id: ComponentId,
+    locale: Option<String>,
+    excluded_product_ids: HashSet<String>,
+    filter: impl Fn(&Product) -> bool,
+) -> Vec<Product> {
+    let agent_filter = get_agent_filter();
+    let get_agents = GetAgents::new(component_id, Some(&agent_filter), false);
+
+    let mut values: Vec<Product> = Vec::new();
+    let mut processed_agent_ids: HashSet<String> = excluded_product_ids;
+
+    while let Some(agents) = get_agents.get_next() {
+        let agent_ids = agents
+            .iter()
+            .filter_map(|a| get_product_agent_id(a.agent_id.agent_id.as_str()))
+            .filter(|n| !processed_agent_ids.contains(n))
+            .collect::<HashSet<_>>();
+
+        let products = get_products(agent_ids.clone(), locale.clone()).await;
+        processed_agent_ids.extend(agent_ids);
+
+        values.extend(products.into_iter().filter(|p| filter(p)));
+    }
+
+    values
+}
+
+async fn get_products(agent_ids: HashSet<String>, locale: Option<String>) -> Vec<Product> {
     let clients: Vec<ProductAgentClient> = agent_ids
         .into_iter()
         .map(|agent_id| ProductAgentClient::get(agent_id.to_string()))
@@ -149,58 +1591,408 @@ async fn get_products(
     let result: Vec<Product> = responses
         .into_iter()
         .flatten()
-        .filter(|p| matcher.matches(p.clone()))
+        .map(|p| match locale.as_deref() {
+            Some(locale) => p.localized(locale),
+            None => p,
+        })
         .collect();
 
-    Ok(result)
+    result
 }
 
 #[agent_definition(mode = "ephemeral")]
 trait ProductSearchAgent {
     fn new() -> Self;
 
-    async fn search(&self, query: String) -> Result<Vec<Product>, String>;
+    async fn search(
+        &self,
+        query: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError>;
+
+    async fn search_localized(
+        &self,
+        query: String,
+        locale: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError>;
+
+    async fn get_category_products(
+        &self,
+        category_id: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError>;
 }
 
 struct ProductSearchAgentImpl {
     component_id: Option<ComponentId>,
 }
 
+impl ProductSearchAgentImpl {
+    // Search products, matching against the text of the locale if given
+    async fn search_products(
+        &self,
+        query: String,
+        locale: Option<String>,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError> {
+        if let Some(component_id) = self.component_id {
+            let SearchOptions {
+                currency,
+                zone,
+                limit,
+                offset,
+                track,
+                price_facets,
+            } = options;
+
+            println!(
+                "searching for products - query: {} locale: {:?} currency: {:?} zone: {:?} limit: {:?} offset: {:?}",
+                query, locale, currency, zone, limit, offset
+            );
+            let dictionary = SearchDictionaryAgentClient::get().get_dictionary().await;
+            let mut matcher = ProductQueryMatcher::new(&query).map_err(SearchError::QuerySyntax)?;
+            matcher.apply_dictionary(&dictionary);
+            matcher
+                .validate_attribute_fields()
+                .await
+                .map_err(SearchError::QuerySyntax)?;
+            matcher.resolve_categories().await;
+
+            let uses_prices = matcher.uses_prices();
+            let price_facets = price_facets.unwrap_or(false);
+            let currency = currency.unwrap_or(CURRENCY_DEFAULT.to_string());
+            let zone_or_default = zone.as_deref().unwrap_or(PRICING_ZONE_DEFAULT);
+
+            // Filters other than price are matched by the scan,
+            // products depending on price filters are matched once their prices are known
+            let mut values = get_all_products(component_id, locale, |p| {
+                zone.as_ref()
+                    .is_none_or(|zone| p.availability.is_zone_allowed(zone))
+                    && matcher.matches_without_prices(p) != Some(false)
+            })
+            .await;
+
+            // Prices of all matching products are only needed for price filters, sort or facets,
+            // otherwise only the returned page is priced
+            let prices_all = matcher.uses_all_prices() || price_facets;
+
+            let prices = if prices_all {
+                get_product_prices(&values, &currency, zone_or_default).await
+            } else {
+                HashMap::new()
+            };
+
+            if uses_prices {
+                values.retain(|p| {
+                    matcher.matches_without_prices(p) == Some(true) || matcher.matches(p, &prices)
+                });
+            }
+
+            let facets = get_facets(&values, price_facets.then_some(&prices));
+            let hits = matcher.get_hits(values, &prices);
+
+            let mut result = ProductSearchResult::new(hits, facets, limit, offset);
+
+            if !prices_all {
+                let products: Vec<Product> =
+                    result.hits.iter().map(|hit| hit.product.clone()).collect();
+                let prices = get_product_prices(&products, &currency, zone_or_default).await;
+                for hit in result.hits.iter_mut() {
+                    hit.price = prices.get(&hit.product.product_id).cloned();
+                }
+            }
+
+            if track.unwrap_or(false) && result.offset == 0 {
+                let search_id = uuid::Uuid::new_v4().to_string();
+                SearchAnalyticsAgentClient::get().trigger_record_search(
+                    search_id.clone(),
+                    query,
+                    result.total,
+                );
+                result.search_id = Some(search_id);
+            }
+
+            Ok(result)
+        } else {
+            Err(SearchError::SearchFailed(SearchFailedError {
+                message: "Component not found".to_string(),
+            }))
+        }
+    }
+}
+
 #[agent_implementation]
 impl ProductSearchAgent for ProductSearchAgentImpl {
     fn new() -> Self {
-        let component_id = resolve_component_id("shopping-rust:shopping");
-        ProductSearchAgentImpl { component_id }
+        ProductSearchAgentImpl {
+            component_id: get_component_id(),
+        }
     }
 
-    async fn search(&self, query: String) -> Result<Vec<Product>, String> {
-        if let Some(component_id) = self.component_id {
-            println!("searching for products - query: {}", query);
+    async fn search(
+        &self,
+        query: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError> {
+        self.search_products(query, None, options).await
+    }
 
-            let mut values: Vec<Product> = Vec::new();
-            let matcher = ProductQueryMatcher::new(&query);
+    async fn search_localized(
+        &self,
+        query: String,
+        locale: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError> {
+        self.search_products(query, Some(locale), options).await
+    }
 
-            let filter = get_agent_filter();
+    async fn get_category_products(
+        &self,
+        category_id: String,
+        options: SearchOptions,
+    ) -> Result<ProductSearchResult, SearchError> {
+        self.search(
+            format!("category:{}", quote_query_value(&category_id)),
+            options,
+        )
+        .await
+    }
+}
 
-            let get_agents = GetAgents::new(component_id, Some(&filter), false);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let mut processed_agent_ids: HashSet<String> = HashSet::new();
+    // Compact form of the parsed query, with explicit grouping
+    fn describe(expr: &QueryExpr) -> String {
+        let join = |exprs: &[QueryExpr], op: &str| {
+            let exprs: Vec<String> = exprs.iter().map(describe).collect();
+            format!("({})", exprs.join(op))
+        };
+        match expr {
+            QueryExpr::Term(term) => term.clone(),
+            QueryExpr::Phrase(phrase) => format!("\"{}\"", phrase),
+            QueryExpr::Field(field, value) => format!("{}:{}", field, value),
+            QueryExpr::Comparison(field, comparison, value) => {
+                format!("{}{:?}{}", field, comparison, value)
+            }
+            QueryExpr::And(exprs) => join(exprs, " AND "),
+            QueryExpr::Or(exprs) => join(exprs, " OR "),
+            QueryExpr::Not(expr) => format!("NOT {}", describe(expr)),
+        }
+    }
 
-            while let Some(agents) = get_agents.get_next() {
-                let agent_ids = agents
-                    .iter()
-                    .filter_map(|a| get_product_agent_id(a.agent_id.agent_id.as_str()))
-                    .filter(|n| !processed_agent_ids.contains(n))
-                    .collect::<HashSet<_>>();
+    fn parse(query: &str) -> ProductQueryMatcher {
+        let Ok(matcher) = ProductQueryMatcher::new(query) else {
+            panic!("query {} is not valid", query);
+        };
+        matcher
+    }
 
-                let products = get_products(agent_ids.clone(), matcher.clone()).await?;
-                processed_agent_ids.extend(agent_ids);
-                values.extend(products);
-            }
+    fn syntax_error(query: &str) -> QuerySyntaxError {
+        let Err(error) = ProductQueryMatcher::new(query) else {
+            panic!("query {} is valid", query);
+        };
+        error
+    }
 
-            Ok(values)
-        } else {
-            Err("Component not found".to_string())
-        }
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            describe(&parse("a b OR c AND d").expr),
+            "((a AND b) OR (c AND d))"
+        );
+        assert_eq!(describe(&parse("a OR b c").expr), "(a OR (b AND c))");
+        assert_eq!(describe(&parse("(a OR b) c").expr), "((a OR b) AND c)");
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(describe(&parse("NOT a b").expr), "(NOT a AND b)");
+        assert_eq!(describe(&parse("a -b OR c").expr), "((a AND NOT b) OR c)");
+        assert_eq!(
+            describe(&parse("-(a OR b) NOT NOT c").expr),
+            "(NOT (a OR b) AND NOT NOT c)"
+        );
+        // minus followed by whitespace is a term, not an operator
+        assert_eq!(describe(&parse("a - b").expr), "(a AND - AND b)");
+    }
+
+    #[test]
+    fn quoted_operators_are_terms() {
+        assert_eq!(describe(&parse("\"OR\" a").expr), "(\"OR\" AND a)");
+        assert_eq!(describe(&parse("a or b").expr), "(a AND or AND b)");
+    }
+
+    #[test]
+    fn unbalanced_parentheses() {
+        let error = syntax_error("(a OR b");
+        assert_eq!(error.message, "Missing closing parenthesis");
+        assert_eq!(error.position, 0);
+
+        let error = syntax_error("a (b (c)");
+        assert_eq!(error.message, "Missing closing parenthesis");
+        assert_eq!(error.position, 2);
+
+        let error = syntax_error("a OR b)");
+        assert_eq!(error.message, "Unexpected closing parenthesis");
+        assert_eq!(error.position, 6);
+
+        let error = syntax_error("()");
+        assert_eq!(error.message, "Unexpected closing parenthesis");
+        assert_eq!(error.position, 1);
+    }
+
+    #[test]
+    fn missing_operands() {
+        let error = syntax_error("a OR");
+        assert_eq!(error.message, "Unexpected end of the query");
+        assert_eq!(error.position, 4);
+
+        let error = syntax_error("a AND OR b");
+        assert_eq!(error.message, "Unexpected operator OR");
+        assert_eq!(error.position, 6);
+
+        assert_eq!(syntax_error("NOT").message, "Unexpected end of the query");
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        assert_eq!(describe(&parse(r#""14\" screen""#).expr), r#""14" screen""#);
+        assert_eq!(
+            describe(&parse(r#"name:"red \"deluxe\" shoe""#).expr),
+            r#"name:red "deluxe" shoe"#
+        );
+        assert_eq!(describe(&parse(r#"a\"b"#).expr), r#"a"b"#);
+
+        let query = format!("brand:{}", quote_query_value(r#"say "hi" \o/"#));
+        assert_eq!(describe(&parse(&query).expr), r#"brand:say "hi" \o/"#);
+    }
+
+    #[test]
+    fn unterminated_quotes_and_escapes() {
+        let error = syntax_error("a \"red shoe");
+        assert_eq!(error.message, "Missing closing quote");
+        assert_eq!(error.position, 2);
+
+        let error = syntax_error("a\\");
+        assert_eq!(error.message, "Escape at the end of the query");
+        assert_eq!(error.position, 1);
+
+        let error = syntax_error("\"a\\");
+        assert_eq!(error.message, "Escape at the end of the query");
+        assert_eq!(error.position, 2);
+    }
+
+    #[test]
+    fn unknown_fields_are_attribute_fields() {
+        let matcher = parse("brand:acme color:red (weight>2 OR NOT Size:xl) rating>=4");
+        assert_eq!(
+            matcher.attribute_fields,
+            vec![
+                ("color".to_string(), 11),
+                ("weight".to_string(), 22),
+                ("size".to_string(), 38),
+            ]
+        );
+        assert!(parse("a b \"c:d\"").attribute_fields.is_empty());
+    }
+
+    #[test]
+    fn field_errors() {
+        assert_eq!(syntax_error(":red").message, "Missing field name");
+        assert_eq!(
+            syntax_error("color:").message,
+            "Missing value of field color"
+        );
+        assert_eq!(
+            syntax_error("brand>2").message,
+            "Field brand does not support comparison"
+        );
+        assert_eq!(
+            syntax_error("on-sale:maybe").message,
+            "Invalid value maybe of field on-sale, expected true or false"
+        );
+        assert_eq!(syntax_error("price>1.2.3").message, "Invalid number 1.2.3");
+    }
+
+    #[test]
+    fn query_options() {
+        let matcher = parse("shoe SORT:Price match:exact");
+        assert_eq!(describe(&matcher.expr), "shoe");
+        assert_eq!(matcher.sort, SearchSort::Price);
+        assert!(matcher.exact);
+
+        // options are only taken from the top level, negated or nested ones are rejected
+        let error = syntax_error("shoe -sort:price");
+        assert_eq!(
+            error.message,
+            "Option sort is only allowed at the top level of the query"
+        );
+        let error = syntax_error("(sort:price)");
+        assert_eq!(error.position, 1);
+
+        let error = syntax_error("a sort:cheapest");
+        assert_eq!(error.message, "Unknown sort cheapest");
+        assert_eq!(error.position, 2);
+        assert_eq!(syntax_error("match:loose").message, "Unknown match loose");
+    }
+
+    #[test]
+    fn stemming() {
+        assert_eq!(stem_word("laptops"), "laptop");
+        assert_eq!(stem_word("batteries"), "battery");
+        assert_eq!(stem_word("boxes"), "box");
+        assert_eq!(stem_word("watches"), "watch");
+        assert_eq!(stem_word("charging"), "charg");
+        assert_eq!(stem_word("wired"), "wir");
+        // short words and words ending in ss, us or is are kept
+        assert_eq!(stem_word("bus"), "bus");
+        assert_eq!(stem_word("glass"), "glass");
+        assert_eq!(stem_word("cactus"), "cactus");
+        assert_eq!(stem_word("ties"), "tie");
+        assert_eq!(stem_word("ring"), "ring");
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("phone", "phone"), 0);
+        assert_eq!(edit_distance("phone", "fone"), 2);
+        assert_eq!(edit_distance("phone", "phnoe"), 1);
+        assert_eq!(edit_distance("cable", "cables"), 1);
+        assert_eq!(edit_distance("", "usb"), 3);
+        assert_eq!(edit_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn fuzzy_edit_limits() {
+        // terms shorter than the fuzzy min length have to match exactly
+        assert_eq!(term_frequency("usb hub", "usv", false), 0);
+        // one edit from the fuzzy min length
+        assert_eq!(term_frequency("mouse pad", "mose", false), 1);
+        assert_eq!(term_frequency("mouse pad", "mous", false), 1);
+        assert_eq!(term_frequency("mouse pad", "mosue", false), 1);
+        assert_eq!(term_frequency("mouse pad", "moseu", false), 0);
+        // two edits from the two edits min length
+        assert_eq!(term_frequency("wireless keyboard", "keybaord", false), 1);
+        assert_eq!(term_frequency("wireless keyboard", "kyebaord", false), 1);
+        assert_eq!(term_frequency("wireless keyboard", "keyboar", false), 1);
+        assert_eq!(term_frequency("wireless keyboard", "kyebrd", false), 0);
+        // stems match regardless of the length
+        assert_eq!(term_frequency("usb cables", "cable", false), 1);
+        // exact matching does not use stems or edits
+        assert_eq!(term_frequency("usb cables", "cabel", true), 0);
+        assert_eq!(term_frequency("mouse pad", "mosue", true), 0);
+        // terms with non alphanumeric characters are not matched fuzzily
+        assert_eq!(term_frequency("usb-c cable", "usb-d", false), 0);
+    }
+
+    #[test]
+    fn term_keys() {
+        assert_eq!(get_term_key("Wireless  Chargers"), "wireless charger");
+        assert_eq!(get_term_key("Café-Tables"), "cafe table");
+        assert_eq!(normalize_text("  Crème   Brûlée ", false), "creme brulee");
+        assert_eq!(normalize_text("  Crème   Brûlée ", true), "crème brûlée");
     }
 }
@@ -0,0 +1,349 @@
+use crate::common::fold_text;
+use crate::product::{Product, ProductRating};
+use crate::product_search::{get_all_products, get_component_id};
+use futures::future::join_all;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+pub const SUGGEST_COMPLETION_LIMIT: usize = 8;
+
+pub const SUGGEST_PRODUCT_LIMIT: usize = 5;
+
+#[derive(Schema, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SuggestionKind {
+    Name,
+    Brand,
+    Tag,
+}
+
+#[derive(Schema, Clone)]
+pub struct CompletionSuggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    pub product_count: u32,
+}
+
+#[derive(Schema, Clone)]
+pub struct ProductSuggestion {
+    pub product_id: String,
+    pub name: String,
+    pub brand: String,
+    pub rating: ProductRating,
+}
+
+#[derive(Schema, Clone)]
+pub struct Suggestions {
+    pub prefix: String,
+    pub completions: Vec<CompletionSuggestion>,
+    pub products: Vec<ProductSuggestion>,
+}
+
+// Indexed text, `key_start` is true if the key starts at the beginning of the text
+struct SuggestEntry {
+    text: String,
+    key_start: bool,
+    product_ids: BTreeSet<String>,
+}
+
+struct IndexedProduct {
+    suggestion: ProductSuggestion,
+    keys: Vec<(String, SuggestionKind)>,
+}
+
+// Prefix index over product names, brands and tags, keys are folded text,
+// names are indexed from each word as well, so `iph` completes `Apple iPhone`
+struct SuggestIndex {
+    entries: BTreeMap<(String, SuggestionKind), SuggestEntry>,
+    products: HashMap<String, IndexedProduct>,
+}
+
+impl SuggestIndex {
+    fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            products: HashMap::new(),
+        }
+    }
+
+    fn get_keys(product: &Product) -> Vec<(String, String, SuggestionKind, bool)> {
+        let mut keys = Vec::new();
+
+        let name = fold_text(product.name.trim());
+        // Words are searched from the end of the previous word, so repeated words get their own keys
+        let mut start = 0;
+        for (i, word) in name.split_whitespace().enumerate() {
+            let word_start = name[start..].find(word).map_or(start, |p| start + p);
+            start = word_start + word.len();
+            keys.push((
+                name[word_start..].to_string(),
+                product.name.trim().to_string(),
+                SuggestionKind::Name,
+                i == 0,
+            ));
+        }
+
+        let brand = product.brand.trim();
+        if !brand.is_empty() {
+            keys.push((
+                fold_text(brand),
+                brand.to_string(),
+                SuggestionKind::Brand,
+                true,
+            ));
+        }
+
+        for tag in product.tags.iter().map(|tag| tag.trim()) {
+            if !tag.is_empty() {
+                keys.push((fold_text(tag), tag.to_string(), SuggestionKind::Tag, true));
+            }
+        }
+
+        keys
+    }
+
+    fn remove_product(&mut self, product_id: &str) {
+        if let Some(indexed) = self.products.remove(product_id) {
+            for key in indexed.keys {
+                let empty = match self.entries.get_mut(&key) {
+                    Some(entry) => {
+                        entry.product_ids.remove(product_id);
+                        entry.product_ids.is_empty()
+                    }
+                    None => false,
+                };
+                if empty {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+    }
+
+    // Index the product, products which are not available are removed
+    fn update_product(&mut self, product: Product) {
+        self.remove_product(&product.product_id);
+
+        if !product.is_available() {
+            return;
+        }
+
+        let mut keys = Vec::new();
+        for (key, text, kind, key_start) in Self::get_keys(&product) {
+            let entry = self
+                .entries
+                .entry((key.clone(), kind))
+                .or_insert(SuggestEntry {
+                    text,
+                    key_start,
+                    product_ids: BTreeSet::new(),
+                });
+            entry.product_ids.insert(product.product_id.clone());
+            keys.push((key, kind));
+        }
+
+        self.products.insert(
+            product.product_id.clone(),
+            IndexedProduct {
+                suggestion: ProductSuggestion {
+                    product_id: product.product_id,
+                    name: product.name,
+                    brand: product.brand,
+                    rating: product.rating,
+                },
+                keys,
+            },
+        );
+    }
+
+    fn suggest(&self, prefix: &str) -> Suggestions {
+        let folded = fold_text(prefix.trim());
+
+        if folded.is_empty() {
+            return Suggestions {
+                prefix: prefix.to_string(),
+                completions: vec![],
+                products: vec![],
+            };
+        }
+
+        let entries: Vec<(&(String, SuggestionKind), &SuggestEntry)> = self
+            .entries
+            .range((folded.clone(), SuggestionKind::Name)..)
+            .take_while(|((key, _), _)| key.starts_with(&folded))
+            .collect();
+
+        // Texts matching from the start rank first, then texts of more products and shorter texts
+        let mut completions: HashMap<(String, SuggestionKind), (bool, BTreeSet<&String>)> =
+            HashMap::new();
+        for ((_, kind), entry) in entries.iter() {
+            let completion = completions
+                .entry((entry.text.clone(), *kind))
+                .or_insert((false, BTreeSet::new()));
+            completion.0 |= entry.key_start;
+            completion.1.extend(entry.product_ids.iter());
+        }
+
+        let mut completions: Vec<(bool, CompletionSuggestion)> = completions
+            .into_iter()
+            .map(|((text, kind), (key_start, product_ids))| {
+                (
+                    key_start,
+                    CompletionSuggestion {
+                        text,
+                        kind,
+                        product_count: product_ids.len() as u32,
+                    },
+                )
+            })
+            .collect();
+        completions.sort_by(|(a_start, a), (b_start, b)| {
+            b_start
+                .cmp(a_start)
+                .then(b.product_count.cmp(&a.product_count))
+                .then(a.text.len().cmp(&b.text.len()))
+                .then(a.text.cmp(&b.text))
+                .then(a.kind.cmp(&b.kind))
+        });
+
+        let product_ids: BTreeSet<&String> = entries
+            .iter()
+            .flat_map(|(_, entry)| entry.product_ids.iter())
+            .collect();
+
+        let mut products: Vec<ProductSuggestion> = product_ids
+            .into_iter()
+            .filter_map(|id| self.products.get(id))
+            .map(|indexed| indexed.suggestion.clone())
+            .collect();
+        products.sort_by(|a, b| {
+            b.rating
+                .average
+                .total_cmp(&a.rating.average)
+                .then(b.rating.count.cmp(&a.rating.count))
+                .then(a.name.cmp(&b.name))
+        });
+        products.truncate(SUGGEST_PRODUCT_LIMIT);
+
+        Suggestions {
+            prefix: prefix.to_string(),
+            completions: completions
+                .into_iter()
+                .map(|(_, completion)| completion)
+                .take(SUGGEST_COMPLETION_LIMIT)
+                .collect(),
+            products,
+        }
+    }
+}
+
+// Count of suggestion index replicas, suggestions are read from a random replica
+pub const SUGGEST_INDEX_REPLICAS: u32 = 4;
+
+// Update the product in all suggestion index replicas, without waiting for the indexes
+pub fn trigger_index_product(product: &Product) {
+    for replica in 0..SUGGEST_INDEX_REPLICAS {
+        ProductSuggestIndexAgentClient::get(replica).trigger_update_product(product.clone());
+    }
+}
+
+// Replica of the suggestion index, all replicas get the same updates
+#[agent_definition]
+trait ProductSuggestIndexAgent {
+    fn new(replica: u32) -> Self;
+
+    fn suggest(&self, prefix: String) -> Suggestions;
+
+    fn update_product(&mut self, product: Product);
+
+    fn remove_product(&mut self, product_id: String);
+
+    fn load_products(&mut self, products: Vec<Product>) -> u32;
+}
+
+struct ProductSuggestIndexAgentImpl {
+    _replica: u32,
+    state: SuggestIndex,
+}
+
+#[agent_implementation]
+impl ProductSuggestIndexAgent for ProductSuggestIndexAgentImpl {
+    fn new(replica: u32) -> Self {
+        ProductSuggestIndexAgentImpl {
+            _replica: replica,
+            state: SuggestIndex::new(),
+        }
+    }
+
+    fn suggest(&self, prefix: String) -> Suggestions {
+        self.state.suggest(&prefix)
+    }
+
+    fn update_product(&mut self, product: Product) {
+        println!("Updating product {} in suggest index", product.product_id);
+        self.state.update_product(product);
+    }
+
+    fn remove_product(&mut self, product_id: String) {
+        println!("Removing product {} from suggest index", product_id);
+        self.state.remove_product(&product_id);
+    }
+
+    // Replace the index with the products
+    fn load_products(&mut self, products: Vec<Product>) -> u32 {
+        println!("Loading {} products to suggest index", products.len());
+
+        let mut index = SuggestIndex::new();
+        for product in products {
+            index.update_product(product);
+        }
+
+        self.state = index;
+        self.state.products.len() as u32
+    }
+}
+
+#[agent_definition(mode = "ephemeral")]
+trait ProductSuggestAgent {
+    fn new() -> Self;
+
+    async fn suggest(&self, prefix: String) -> Suggestions;
+
+    async fn rebuild_index(&self) -> Result<u32, String>;
+}
+
+struct ProductSuggestAgentImpl {}
+
+#[agent_implementation]
+impl ProductSuggestAgent for ProductSuggestAgentImpl {
+    fn new() -> Self {
+        ProductSuggestAgentImpl {}
+    }
+
+    async fn suggest(&self, prefix: String) -> Suggestions {
+        let replica = (uuid::Uuid::new_v4().as_u128() % SUGGEST_INDEX_REPLICAS as u128) as u32;
+
+        ProductSuggestIndexAgentClient::get(replica)
+            .suggest(prefix)
+            .await
+    }
+
+    // Index all available products in all replicas, for products created before the index
+    async fn rebuild_index(&self) -> Result<u32, String> {
+        println!("Rebuilding suggest index");
+
+        let component_id = get_component_id().ok_or("Component not found".to_string())?;
+        let products = get_all_products(component_id, None, |p| p.is_available()).await;
+
+        let mut clients: Vec<ProductSuggestIndexAgentClient> = (0..SUGGEST_INDEX_REPLICAS)
+            .map(ProductSuggestIndexAgentClient::get)
+            .collect();
+
+        let counts = join_all(
+            clients
+                .iter_mut()
+                .map(|client| client.load_products(products.clone())),
+        )
+        .await;
+
+        Ok(counts.into_iter().min().unwrap_or(0))
+    }
+}
@@ -0,0 +1,351 @@
+use crate::common::fold_text;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::{BTreeMap, HashMap};
+
+// Stats of days older than the retention are dropped
+pub const SEARCH_ANALYTICS_RETENTION_DAYS: u32 = 90;
+
+// Add to cart is recorded for searches within the window, older searches are dropped
+pub const SEARCH_ADD_TO_CART_WINDOW_HOURS: u32 = 24;
+
+// Max count of queries in the top and zero-result query lists
+pub const SEARCH_ANALYTICS_QUERY_LIMIT: usize = 20;
+
+// Normalize the query, so queries differing only by case, diacritics or whitespace are the same
+pub fn normalize_query(query: &str) -> String {
+    fold_text(&query.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// Recent search, kept for recording add to cart within the window
+#[derive(Schema, Clone)]
+pub struct SearchRecord {
+    pub search_id: String,
+    pub query: String,
+    pub result_count: u32,
+    // Products added to the cart from the search results
+    pub added_product_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Stats of searches, conversion rate is the share of searches followed by an add to cart
+#[derive(Schema, Clone)]
+pub struct QueryStats {
+    pub query: String,
+    pub search_count: u32,
+    pub add_to_cart_count: u32,
+    pub conversion_rate: f32,
+}
+
+#[derive(Schema, Clone)]
+pub struct DailySearchStats {
+    pub date: chrono::NaiveDate,
+    pub search_count: u32,
+    pub zero_result_count: u32,
+    pub add_to_cart_count: u32,
+    pub conversion_rate: f32,
+}
+
+#[derive(Schema, Clone)]
+pub struct SearchAnalyticsReport {
+    pub from: chrono::DateTime<chrono::Utc>,
+    pub to: chrono::DateTime<chrono::Utc>,
+    pub search_count: u32,
+    pub zero_result_count: u32,
+    pub add_to_cart_count: u32,
+    pub conversion_rate: f32,
+    pub top_queries: Vec<QueryStats>,
+    pub zero_result_queries: Vec<QueryStats>,
+    pub daily: Vec<DailySearchStats>,
+}
+
+#[derive(Default)]
+struct StatsCounter {
+    search_count: u32,
+    zero_result_count: u32,
+    converted_count: u32,
+    add_to_cart_count: u32,
+}
+
+impl StatsCounter {
+    fn add_search(&mut self, result_count: u32) {
+        self.search_count += 1;
+        if result_count == 0 {
+            self.zero_result_count += 1;
+        }
+    }
+
+    // The first product added to the cart converts the search
+    fn add_to_cart(&mut self, converted: bool) {
+        if converted {
+            self.converted_count += 1;
+        }
+        self.add_to_cart_count += 1;
+    }
+
+    fn add(&mut self, other: &StatsCounter) {
+        self.search_count += other.search_count;
+        self.zero_result_count += other.zero_result_count;
+        self.converted_count += other.converted_count;
+        self.add_to_cart_count += other.add_to_cart_count;
+    }
+
+    fn conversion_rate(&self) -> f32 {
+        if self.search_count == 0 {
+            0.0
+        } else {
+            self.converted_count as f32 / self.search_count as f32
+        }
+    }
+
+    fn query_stats(&self, query: String) -> QueryStats {
+        QueryStats {
+            query,
+            search_count: self.search_count,
+            add_to_cart_count: self.add_to_cart_count,
+            conversion_rate: self.conversion_rate(),
+        }
+    }
+}
+
+// Queries with the most searches, by the count of matching searches
+fn get_top_queries(
+    counters: &HashMap<&String, StatsCounter>,
+    count: impl Fn(&StatsCounter) -> u32,
+) -> Vec<QueryStats> {
+    let mut queries: Vec<(&String, &StatsCounter)> = counters
+        .iter()
+        .filter(|(_, counter)| count(counter) > 0)
+        .map(|(query, counter)| (*query, counter))
+        .collect();
+    queries.sort_by(|(qa, a), (qb, b)| count(b).cmp(&count(a)).then(qa.cmp(qb)));
+
+    queries
+        .into_iter()
+        .take(SEARCH_ANALYTICS_QUERY_LIMIT)
+        .map(|(query, counter)| counter.query_stats(query.clone()))
+        .collect()
+}
+
+// Counters of a day, in total and per normalized query
+#[derive(Default)]
+struct DailyCounters {
+    total: StatsCounter,
+    queries: HashMap<String, StatsCounter>,
+}
+
+impl DailyCounters {
+    fn get_mut(&mut self, query: &str) -> [&mut StatsCounter; 2] {
+        [
+            &mut self.total,
+            self.queries.entry(query.to_string()).or_default(),
+        ]
+    }
+}
+
+struct SearchAnalytics {
+    days: BTreeMap<chrono::NaiveDate, DailyCounters>,
+    recent_searches: Vec<SearchRecord>,
+}
+
+impl SearchAnalytics {
+    fn new() -> Self {
+        Self {
+            days: BTreeMap::new(),
+            recent_searches: vec![],
+        }
+    }
+
+    fn add_search(&mut self, record: SearchRecord) {
+        let retention_start = (record.created_at
+            - chrono::Duration::days(SEARCH_ANALYTICS_RETENTION_DAYS as i64))
+        .date_naive();
+        self.days = self.days.split_off(&retention_start);
+
+        let window_start =
+            record.created_at - chrono::Duration::hours(SEARCH_ADD_TO_CART_WINDOW_HOURS as i64);
+        self.recent_searches
+            .retain(|s| s.created_at >= window_start);
+
+        for counter in self
+            .days
+            .entry(record.created_at.date_naive())
+            .or_default()
+            .get_mut(&record.query)
+        {
+            counter.add_search(record.result_count);
+        }
+        self.recent_searches.push(record);
+    }
+
+    fn get_search(&self, search_id: &str) -> Option<&SearchRecord> {
+        // Searches are in the order of recording, add to cart usually follows a recent search
+        self.recent_searches
+            .iter()
+            .rev()
+            .find(|s| s.search_id == search_id)
+    }
+
+    // Count the product added to the cart in the stats of the day and query of the search,
+    // returns false if the search is not recent
+    fn add_to_cart(&mut self, search_id: &str, product_id: String) -> bool {
+        let Some(search) = self
+            .recent_searches
+            .iter_mut()
+            .rev()
+            .find(|s| s.search_id == search_id)
+        else {
+            return false;
+        };
+
+        if !search.added_product_ids.contains(&product_id) {
+            let converted = search.added_product_ids.is_empty();
+            search.added_product_ids.push(product_id);
+
+            for counter in self
+                .days
+                .entry(search.created_at.date_naive())
+                .or_default()
+                .get_mut(&search.query)
+            {
+                counter.add_to_cart(converted);
+            }
+        }
+        true
+    }
+
+    fn get_report(&self, days: u32) -> SearchAnalyticsReport {
+        // Report covers whole days, today and the days before it
+        let to = chrono::Utc::now();
+        let to_date = to.date_naive();
+        let from_date = to_date
+            - chrono::Duration::days(days.clamp(1, SEARCH_ANALYTICS_RETENTION_DAYS) as i64 - 1);
+        let from = from_date.and_time(chrono::NaiveTime::MIN).and_utc();
+
+        let mut total = StatsCounter::default();
+        let mut queries: HashMap<&String, StatsCounter> = HashMap::new();
+
+        let daily: Vec<(&chrono::NaiveDate, &DailyCounters)> =
+            self.days.range(from_date..=to_date).collect();
+
+        for (_, counters) in daily.iter() {
+            total.add(&counters.total);
+            for (query, counter) in counters.queries.iter() {
+                queries.entry(query).or_default().add(counter);
+            }
+        }
+
+        SearchAnalyticsReport {
+            from,
+            to,
+            search_count: total.search_count,
+            zero_result_count: total.zero_result_count,
+            add_to_cart_count: total.add_to_cart_count,
+            conversion_rate: total.conversion_rate(),
+            top_queries: get_top_queries(&queries, |counter| counter.search_count),
+            zero_result_queries: get_top_queries(&queries, |counter| counter.zero_result_count),
+            daily: daily
+                .into_iter()
+                .map(|(date, counters)| DailySearchStats {
+                    date: *date,
+                    search_count: counters.total.search_count,
+                    zero_result_count: counters.total.zero_result_count,
+                    add_to_cart_count: counters.total.add_to_cart_count,
+                    conversion_rate: counters.total.conversion_rate(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub struct SearchNotFoundError {
+    pub message: String,
+    pub search_id: String,
+}
+
+impl SearchNotFoundError {
+    fn new(search_id: String) -> SearchNotFoundError {
+        SearchNotFoundError {
+            message: "Search not found".to_string(),
+            search_id,
+        }
+    }
+}
+
+#[derive(Schema, Clone)]
+pub enum RecordAddToCartError {
+    SearchNotFound(SearchNotFoundError),
+}
+
+#[agent_definition]
+trait SearchAnalyticsAgent {
+    fn new() -> Self;
+
+    fn record_search(&mut self, search_id: String, query: String, result_count: u32);
+
+    fn record_add_to_cart(
+        &mut self,
+        search_id: String,
+        product_id: String,
+    ) -> Result<(), RecordAddToCartError>;
+
+    fn get_search(&self, search_id: String) -> Option<SearchRecord>;
+
+    fn get_report(&self, days: u32) -> SearchAnalyticsReport;
+}
+
+struct SearchAnalyticsAgentImpl {
+    state: SearchAnalytics,
+}
+
+#[agent_implementation]
+impl SearchAnalyticsAgent for SearchAnalyticsAgentImpl {
+    fn new() -> Self {
+        SearchAnalyticsAgentImpl {
+            state: SearchAnalytics::new(),
+        }
+    }
+
+    fn record_search(&mut self, search_id: String, query: String, result_count: u32) {
+        let query = normalize_query(&query);
+        println!(
+            "Recording search {} - query: {} results: {}",
+            search_id, query, result_count
+        );
+
+        self.state.add_search(SearchRecord {
+            search_id,
+            query,
+            result_count,
+            added_product_ids: vec![],
+            created_at: chrono::Utc::now(),
+        });
+    }
+
+    fn record_add_to_cart(
+        &mut self,
+        search_id: String,
+        product_id: String,
+    ) -> Result<(), RecordAddToCartError> {
+        println!(
+            "Recording add to cart of product {} from search {}",
+            product_id, search_id
+        );
+
+        if self.state.add_to_cart(&search_id, product_id) {
+            Ok(())
+        } else {
+            Err(RecordAddToCartError::SearchNotFound(
+                SearchNotFoundError::new(search_id),
+            ))
+        }
+    }
+
+    fn get_search(&self, search_id: String) -> Option<SearchRecord> {
+        self.state.get_search(&search_id).cloned()
+    }
+
+    fn get_report(&self, days: u32) -> SearchAnalyticsReport {
+        self.state.get_report(days)
+    }
+}
@@ -0,0 +1,276 @@
+use crate::common::fold_text;
+use crate::product_search::validate_query;
+use golem_rust::{agent_definition, agent_implementation, Schema};
+use std::collections::HashSet;
+
+// Synonyms of the term, one-way synonyms are used when searching for the term only,
+// two-way synonyms make the term and all its synonyms interchangeable
+#[derive(Schema, Clone)]
+pub struct SynonymRule {
+    pub term: String,
+    pub synonyms: Vec<String>,
+    pub two_way: bool,
+}
+
+// Rewrite of the terms of the pattern in search queries, the replacement is query syntax,
+// e.g. `cheap` to `price<25`, terms are compared like synonyms and field values are not rewritten
+#[derive(Schema, Clone)]
+pub struct RewriteRule {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct SearchDictionary {
+    pub synonyms: Vec<SynonymRule>,
+    pub stop_words: Vec<String>,
+    pub rewrites: Vec<RewriteRule>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SearchDictionary {
+    fn new() -> Self {
+        Self {
+            synonyms: vec![],
+            stop_words: vec![],
+            rewrites: vec![],
+            updated_at: chrono::Utc::now(),
+        }
+    }
+}
+
+// Whitespace is collapsed and text is folded, so entries differing only by case or diacritics are the same
+fn get_entry_key(text: &str) -> String {
+    fold_text(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn normalize_entry(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn validate_synonyms(term: &str, synonyms: &[String]) -> Result<(), String> {
+    if term.is_empty() {
+        return Err("Synonym term is empty".to_string());
+    }
+
+    let mut keys: HashSet<String> = HashSet::from([get_entry_key(term)]);
+    for synonym in synonyms {
+        if synonym.is_empty() {
+            return Err(format!("Synonym of term {} is empty", term));
+        }
+        if !keys.insert(get_entry_key(synonym)) {
+            return Err(format!(
+                "Synonym {} of term {} defined more than once",
+                synonym, term
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_stop_words(words: &[String]) -> Result<(), String> {
+    for word in words {
+        if word.is_empty() {
+            return Err("Stop word is empty".to_string());
+        }
+        if word.contains(char::is_whitespace) {
+            return Err(format!("Stop word {} has more than one word", word));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_rewrite(pattern: &str, replacement: &str) -> Result<(), String> {
+    if pattern.is_empty() {
+        return Err("Rewrite pattern is empty".to_string());
+    }
+    if !pattern.starts_with(char::is_alphanumeric) || !pattern.ends_with(char::is_alphanumeric) {
+        return Err(format!(
+            "Rewrite pattern {} does not start and end with a letter or digit",
+            pattern
+        ));
+    }
+
+    validate_query(replacement).map_err(|e| {
+        format!(
+            "Rewrite replacement {} is not a valid query: {} at position {}",
+            replacement, e.message, e.position
+        )
+    })
+}
+
+#[derive(Schema, Clone)]
+pub struct SearchDictionaryNotValidError {
+    pub message: String,
+}
+
+#[derive(Schema, Clone)]
+pub struct DictionaryEntryNotFoundError {
+    pub message: String,
+    pub entry: String,
+}
+
+impl DictionaryEntryNotFoundError {
+    fn new(entry: String) -> DictionaryEntryNotFoundError {
+        DictionaryEntryNotFoundError {
+            message: "Dictionary entry not found".to_string(),
+            entry,
+        }
+    }
+}
+
+#[agent_definition]
+trait SearchDictionaryAgent {
+    fn new() -> Self;
+
+    fn get_dictionary(&self) -> SearchDictionary;
+
+    fn set_synonyms(
+        &mut self,
+        term: String,
+        synonyms: Vec<String>,
+        two_way: bool,
+    ) -> Result<(), SearchDictionaryNotValidError>;
+
+    fn remove_synonyms(&mut self, term: String) -> Result<(), DictionaryEntryNotFoundError>;
+
+    fn set_stop_words(&mut self, words: Vec<String>) -> Result<(), SearchDictionaryNotValidError>;
+
+    fn set_rewrite(
+        &mut self,
+        pattern: String,
+        replacement: String,
+    ) -> Result<(), SearchDictionaryNotValidError>;
+
+    fn remove_rewrite(&mut self, pattern: String) -> Result<(), DictionaryEntryNotFoundError>;
+}
+
+struct SearchDictionaryAgentImpl {
+    state: SearchDictionary,
+}
+
+#[agent_implementation]
+impl SearchDictionaryAgent for SearchDictionaryAgentImpl {
+    fn new() -> Self {
+        SearchDictionaryAgentImpl {
+            state: SearchDictionary::new(),
+        }
+    }
+
+    fn get_dictionary(&self) -> SearchDictionary {
+        self.state.clone()
+    }
+
+    fn set_synonyms(
+        &mut self,
+        term: String,
+        synonyms: Vec<String>,
+        two_way: bool,
+    ) -> Result<(), SearchDictionaryNotValidError> {
+        println!("Setting synonyms of term {}", term);
+
+        let term = normalize_entry(&term);
+        let synonyms: Vec<String> = synonyms.iter().map(|s| normalize_entry(s)).collect();
+
+        validate_synonyms(&term, &synonyms)
+            .map_err(|message| SearchDictionaryNotValidError { message })?;
+
+        let key = get_entry_key(&term);
+        let rule = SynonymRule {
+            term,
+            synonyms,
+            two_way,
+        };
+        match self
+            .state
+            .synonyms
+            .iter_mut()
+            .find(|r| get_entry_key(&r.term) == key)
+        {
+            Some(existing) => *existing = rule,
+            None => self.state.synonyms.push(rule),
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn remove_synonyms(&mut self, term: String) -> Result<(), DictionaryEntryNotFoundError> {
+        println!("Removing synonyms of term {}", term);
+
+        let key = get_entry_key(&term);
+        let count = self.state.synonyms.len();
+        self.state
+            .synonyms
+            .retain(|rule| get_entry_key(&rule.term) != key);
+
+        if self.state.synonyms.len() == count {
+            return Err(DictionaryEntryNotFoundError::new(term));
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn set_stop_words(&mut self, words: Vec<String>) -> Result<(), SearchDictionaryNotValidError> {
+        println!("Setting {} stop words", words.len());
+
+        let words: Vec<String> = words.iter().map(|w| w.trim().to_lowercase()).collect();
+
+        validate_stop_words(&words).map_err(|message| SearchDictionaryNotValidError { message })?;
+
+        let mut keys: HashSet<String> = HashSet::new();
+        self.state.stop_words = words
+            .into_iter()
+            .filter(|word| keys.insert(get_entry_key(word)))
+            .collect();
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn set_rewrite(
+        &mut self,
+        pattern: String,
+        replacement: String,
+    ) -> Result<(), SearchDictionaryNotValidError> {
+        println!("Setting rewrite of {} to {}", pattern, replacement);
+
+        let pattern = normalize_entry(&pattern);
+        let replacement = replacement.trim().to_string();
+
+        validate_rewrite(&pattern, &replacement)
+            .map_err(|message| SearchDictionaryNotValidError { message })?;
+
+        let rule = RewriteRule {
+            pattern,
+            replacement,
+        };
+        match self
+            .state
+            .rewrites
+            .iter_mut()
+            .find(|r| r.pattern.eq_ignore_ascii_case(&rule.pattern))
+        {
+            Some(existing) => *existing = rule,
+            None => self.state.rewrites.push(rule),
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    fn remove_rewrite(&mut self, pattern: String) -> Result<(), DictionaryEntryNotFoundError> {
+        println!("Removing rewrite of {}", pattern);
+
+        let normalized = normalize_entry(&pattern);
+        let count = self.state.rewrites.len();
+        self.state
+            .rewrites
+            .retain(|rule| !rule.pattern.eq_ignore_ascii_case(&normalized));
+
+        if self.state.rewrites.len() == count {
+            return Err(DictionaryEntryNotFoundError::new(pattern));
+        }
+        self.state.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+}
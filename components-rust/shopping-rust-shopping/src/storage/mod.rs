@@ -0,0 +1,51 @@
+use golem_rust::bindings::wasi::blobstore::blobstore;
+use golem_rust::bindings::wasi::blobstore::container::Container;
+use golem_rust::bindings::wasi::blobstore::types::OutgoingValue;
+
+// Max size of a single blocking write to the blob output stream
+const WRITE_CHUNK_SIZE: usize = 4096;
+
+// Get the blobstore container, creating it if it does not exist yet
+fn get_container(container_name: &str) -> Result<Container, String> {
+    if blobstore::container_exists(container_name)? {
+        blobstore::get_container(container_name)
+    } else {
+        blobstore::create_container(container_name)
+    }
+}
+
+pub fn write_object(container_name: &str, object_name: &str, data: &[u8]) -> Result<(), String> {
+    let container = get_container(container_name)?;
+    let value = OutgoingValue::new_outgoing_value();
+
+    {
+        let stream = value
+            .outgoing_value_write_body()
+            .map_err(|_| "Failed to open object stream".to_string())?;
+
+        for chunk in data.chunks(WRITE_CHUNK_SIZE) {
+            stream
+                .blocking_write_and_flush(chunk)
+                .map_err(|e| format!("Failed to write object: {:?}", e))?;
+        }
+    }
+
+    container.write_data(object_name, &value)
+}
+
+pub fn read_object(container_name: &str, object_name: &str) -> Result<Vec<u8>, String> {
+    let container = get_container(container_name)?;
+    let info = container.object_info(object_name)?;
+
+    if info.size == 0 {
+        return Ok(vec![]);
+    }
+
+    container
+        .get_data(object_name, 0, info.size - 1)?
+        .incoming_value_consume_sync()
+}
+
+pub fn delete_object(container_name: &str, object_name: &str) -> Result<(), String> {
+    get_container(container_name)?.delete_object(object_name)
+}
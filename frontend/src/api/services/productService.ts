@@ -9,16 +9,106 @@ import {
 } from "./pricingService";
 import { dateTimeToDate } from "@/types/datetime.ts";
 
+export interface ProductMedia {
+  "media-id": string;
+  kind: string;
+  "content-type": string;
+  "alt-text"?: string;
+  width?: number;
+  height?: number;
+  size: number;
+  primary: boolean;
+}
+
+export interface MediaContent {
+  "media-id": string;
+  "content-type": string;
+  data: string;
+}
+
 export interface Product {
   "product-id": string;
   name: string;
   brand: string;
   description: string;
   tags: string[];
+  media?: ProductMedia[];
   pricing?: Pricing;
   bestPrice?: number;
+  // Effective price in the searched currency and zone, set for search results
+  price?: EffectivePrice;
+}
+
+export interface EffectivePrice {
+  price: number;
+  currency: string;
+  zone: string;
+  "regular-price"?: number;
+  "on-sale": boolean;
+}
+
+export interface ProductSearchHit {
+  product: Product;
+  score: number;
+  price?: EffectivePrice;
+}
+
+export interface FacetCount {
+  value: string;
+  count: number;
+  filter: string;
+}
+
+export interface PriceFacetCount {
+  from: number;
+  to?: number;
+  count: number;
+  filter: string;
+}
+
+export interface ProductSearchFacets {
+  brands: FacetCount[];
+  tags: FacetCount[];
+  categories: FacetCount[];
+  prices: PriceFacetCount[];
+}
+
+export interface ProductSearchResult {
+  "search-id"?: string;
+  hits: ProductSearchHit[];
+  facets: ProductSearchFacets;
+  total: number;
+  limit: number;
+  offset: number;
+  "next-offset"?: number;
+}
+
+export interface CompletionSuggestion {
+  text: string;
+  kind: string;
+  "product-count": number;
+}
+
+export interface ProductSuggestion {
+  "product-id": string;
+  name: string;
+  brand: string;
+  rating: { average: number; count: number };
+}
+
+export interface Suggestions {
+  prefix: string;
+  completions: CompletionSuggestion[];
+  products: ProductSuggestion[];
 }
 
+// Max page size supported by the search API
+export const SEARCH_LIMIT_MAX = 100;
+
+// Currency and pricing zone used by search if no price preferences are set
+export const CURRENCY_DEFAULT = "USD";
+export const PRICING_ZONE_DEFAULT = "global";
+
 const enhanceWithPricing = async (
   product: Product,
   options?: PriceFilterOptions,
@@ -39,35 +129,92 @@ const enhanceWithPricing = async (
   }
 };
 
+// Products of a search, `searchId` identifies the recorded search of tracked searches
+export interface ProductSearchPage {
+  products: Product[];
+  searchId?: string;
+}
+
+// One page of the search results, only the tracked search request is recorded
+const searchProductsPage = async (
+  query: string,
+  options: PriceFilterOptions | undefined,
+  track: boolean,
+  limit: number,
+  offset: number,
+): Promise<ProductSearchResult> => {
+  const response = await apiClient.get(
+    `/v1/product/search?query=${encodeURIComponent(query)}` +
+      `&currency=${encodeURIComponent(options?.currency ?? CURRENCY_DEFAULT)}` +
+      `&zone=${encodeURIComponent(options?.zone ?? PRICING_ZONE_DEFAULT)}` +
+      `&limit=${limit}&offset=${offset}&track=${track}`,
+  );
+  return response.ok;
+};
+
+// All products matching the query, pages are followed until there is no next offset
 export const searchProducts = async (
   query: string,
   options?: PriceFilterOptions,
-): Promise<Product[]> => {
+  track = false,
+): Promise<ProductSearchPage> => {
   try {
-    const response = await apiClient.get(
-      `/v1/product/search?query=${encodeURIComponent(query)}`,
+    // Only the first page is tracked, so the search is recorded once
+    const first = await searchProductsPage(
+      query,
+      options,
+      track,
+      SEARCH_LIMIT_MAX,
+      0,
     );
-    const products: Product[] = response.ok;
-
-    // Get pricing for all products in batch
-    const productIds = products.map((p) => p["product-id"]);
-    const pricingMap = await getBatchPricing(productIds);
+    const hits: ProductSearchHit[] = [...first.hits];
+    let nextOffset = first["next-offset"];
+    while (nextOffset !== undefined && nextOffset !== null) {
+      const page = await searchProductsPage(
+        query,
+        options,
+        false,
+        SEARCH_LIMIT_MAX,
+        nextOffset,
+      );
+      hits.push(...page.hits);
+      nextOffset = page["next-offset"];
+    }
 
-    // Merge products with their pricing
-    return products.map((product) => {
-      const pricing = pricingMap[product["product-id"]];
-      return {
-        ...product,
-        pricing,
-        bestPrice: pricing ? getBestPrice(pricing, options) : undefined,
-      };
-    });
+    // Hits are priced by the search, in the requested currency and zone
+    return {
+      products: hits.map((hit) => ({
+        ...hit.product,
+        price: hit.price,
+        bestPrice: hit.price?.price,
+      })),
+      searchId: first["search-id"],
+    };
   } catch (error) {
     console.error("Error searching products:", error);
     throw error;
   }
 };
 
+// Record add to cart of a product from the results of a tracked search
+export const recordSearchAddToCart = async (
+  searchId: string,
+  productId: string,
+): Promise<void> => {
+  await apiClient.post(
+    `/v1/search-analytics/searches/${encodeURIComponent(searchId)}/add-to-cart`,
+    { "product-id": productId },
+  );
+};
+
+// Autocomplete completions and top products for the typed prefix
+export const suggestProducts = async (prefix: string): Promise<Suggestions> => {
+  const response = await apiClient.get(
+    `/v1/product/suggest?prefix=${encodeURIComponent(prefix)}`,
+  );
+  return response.ok;
+};
+
 export const getProductById = async (
   productId: string,
   includePricing = true,
@@ -96,53 +243,51 @@ export const getProductWithPricing = (
   options?: PriceFilterOptions,
 ): Promise<Product> => getProductById(productId, true, options);
 
-// Get multiple products by IDs with their pricing
+export interface ProductBatch {
+  products: Product[];
+  "missing-ids": string[];
+}
+
+// Get multiple products by IDs with their pricing, in the order of the IDs
 export const getProductsByIds = async (
     productIds: string[],
     options?: PriceFilterOptions,
 ): Promise<Product[]> => {
   try {
-    // Get all products, handling individual failures
-    const productPromises = productIds.map(id =>
-        getProductById(id, false)
-            .then(product => ({ success: true, product }))
-            .catch(error => {
-              console.warn(`Failed to fetch product ${id}:`, error.message);
-              return { success: false, id, error };
-            })
-    );
+    if (productIds.length === 0) {
+      return [];
+    }
 
-    const results = await Promise.all(productPromises);
+    const response = await apiClient.get(
+        `/v1/product?ids=${productIds.map(encodeURIComponent).join(",")}`,
+    );
+    const batch: ProductBatch = response.ok;
 
-    // Filter out failed fetches and extract successful products
-    const validProducts = results
-        .filter((result): result is { success: true; product: Product } => result.success)
-        .map(result => result.product);
+    if (batch["missing-ids"].length > 0) {
+      console.warn(`Products not found: ${batch["missing-ids"].join(", ")}`);
+    }
 
-    if (validProducts.length === 0) {
+    if (batch.products.length === 0) {
       console.warn('No products were successfully fetched');
       return [];
     }
 
-    // Get pricing only for successfully fetched products
-    const validProductIds = validProducts.map(p => p["product-id"]);
+    // Get pricing only for found products
+    const validProductIds = batch.products.map(p => p["product-id"]);
     const pricingMap = await getBatchPricing(validProductIds).catch(error => {
       console.warn('Failed to fetch batch pricing, continuing without pricing', error);
       return {}; // Return empty pricing map if batch pricing fails
     });
 
     // Merge products with their pricing
-    const result: Record<string, Product> = {};
-    validProducts.forEach((product) => {
+    return batch.products.map((product) => {
       const pricing = pricingMap[product["product-id"]];
-      result[product["product-id"]] = {
+      return {
         ...product,
         pricing,
         bestPrice: pricing ? getBestPrice(pricing, options) : undefined,
       };
     });
-
-    return Object.values(result);
   } catch (error) {
     console.error('Unexpected error in getProductsByIds:', error);
     throw error; // Re-throw for the caller to handle
@@ -153,6 +298,7 @@ export const getProductBestPrice = (
   product: Product,
   options?: PriceFilterOptions,
 ): string => {
+  if (product.price) return product.price.price.toFixed(2);
   if (!product.pricing) return "0.00";
   const price = getBestPrice(product.pricing, options);
   return price?.toFixed(2) || "0.00";
@@ -162,6 +308,9 @@ export const getProductOriginalPrice = (
   product: Product,
   options?: PriceFilterOptions,
 ): string => {
+  if (product.price) {
+    return (product.price["regular-price"] ?? product.price.price).toFixed(2);
+  }
   if (!product.pricing) return getProductBestPrice(product, options);
 
   // Filter list prices by currency and zone if provided
@@ -185,6 +334,7 @@ export const isProductOnSale = (
   product: Product,
   options?: PriceFilterOptions,
 ): boolean => {
+  if (product.price) return product.price["on-sale"];
   if (!product.pricing?.["sale-prices"]?.length) return false;
 
   // Filter sale prices by date, currency and zone
@@ -238,3 +388,36 @@ export const getProductImage = (product: { name: string }): string => {
   const imageId = Math.abs(nameHash) % 1000;
   return `https://picsum.photos/seed/${imageId}/300/200`;
 };
+
+export const getPrimaryMedia = (product: Product): ProductMedia | undefined =>
+  product.media?.find((media) => media.primary);
+
+export const getMediaContent = async (
+  productId: string,
+  mediaId: string,
+): Promise<MediaContent> => {
+  const response = await apiClient.get(
+    `/v1/product/${productId}/media/${encodeURIComponent(mediaId)}`,
+  );
+  return response.ok;
+};
+
+// Primary image of the product as data url, falls back to a placeholder image
+export const getProductImageUrl = async (product: Product): Promise<string> => {
+  const media = getPrimaryMedia(product);
+  if (!media) return getProductImage(product);
+
+  try {
+    const content = await getMediaContent(
+      product["product-id"],
+      media["media-id"],
+    );
+    return `data:${content["content-type"]};base64,${content.data}`;
+  } catch (error) {
+    console.warn(
+      `Failed to fetch media ${media["media-id"]} of product ${product["product-id"]}:`,
+      error,
+    );
+    return getProductImage(product);
+  }
+};
@@ -4,22 +4,30 @@ import type { PriceFilterOptions } from "@/api/services/pricingService";
 import {
   searchProducts,
   getProductById,
+  recordSearchAddToCart,
   type Product,
 } from "@/api/services/productService";
 
 export const useProductStore = defineStore("products", () => {
   const products = ref<Product[]>([]);
+  // Recorded search of the current products, if the search is tracked
+  const searchId = ref<string | null>(null);
   const currentProduct = ref<Product | null>(null);
   const isLoading = ref(false);
   const error = ref<Error | null>(null);
 
-  const search = async (query: string, options?: PriceFilterOptions) => {
+  const search = async (
+    query: string,
+    options?: PriceFilterOptions,
+    track = false,
+  ) => {
     isLoading.value = true;
     error.value = null;
 
     try {
-      const results = await searchProducts(query, options);
-      products.value = results;
+      const results = await searchProducts(query, options, track);
+      products.value = results.products;
+      searchId.value = results.searchId ?? null;
     } catch (err) {
       error.value = err as Error;
       console.error("Error searching products:", err);
@@ -48,8 +56,25 @@ export const useProductStore = defineStore("products", () => {
     }
   };
 
+  // Record add to cart of a product of the tracked search results
+  const recordAddToCart = async (productId: string) => {
+    if (
+      !searchId.value ||
+      !products.value.some((p) => p["product-id"] === productId)
+    ) {
+      return;
+    }
+
+    try {
+      await recordSearchAddToCart(searchId.value, productId);
+    } catch (err) {
+      console.error("Error recording search add to cart:", err);
+    }
+  };
+
   const clearProducts = () => {
     products.value = [];
+    searchId.value = null;
     currentProduct.value = null;
   };
 
@@ -57,11 +82,13 @@ export const useProductStore = defineStore("products", () => {
 
   return {
     products,
+    searchId,
     currentProduct,
     isLoading,
     error,
     hasProducts,
     search,
+    recordAddToCart,
     fetchProduct,
     clearProducts,
   };